@@ -8,6 +8,7 @@ pub use action_evm_transaction::*;
 mod action_evm_transaction {
     use alloc::{format, str::FromStr, string::String, vec::Vec};
     use ethabi::{ParamType, Token};
+    use ownership::{Ownable, Ownership};
     use pink_extension as pink;
     use pink_json as json;
     use pink_web3::{
@@ -18,12 +19,21 @@ mod action_evm_transaction {
     use scale::{Decode, Encode};
     use this_crate::{version_tuple, VersionTuple};
 
+    /// Emitted when the ownership is accepted or renounced, `None` means no owner.
+    #[ink(event)]
+    pub struct OwnershipTransferred {
+        #[ink(topic)]
+        pub previous_owner: Option<AccountId>,
+        #[ink(topic)]
+        pub new_owner: Option<AccountId>,
+    }
+
     /// Defines the storage of your contract.
     /// Add new fields to the below struct in order
     /// to add new static storage fields to your contract.
     #[ink(storage)]
     pub struct ActionEvmTransaction {
-        owner: AccountId,
+        ownership: Ownership,
         config: Option<Config>,
     }
 
@@ -53,7 +63,7 @@ mod action_evm_transaction {
         #[ink(constructor)]
         pub fn default() -> Self {
             Self {
-                owner: Self::env().caller(),
+                ownership: Ownership::new(Self::env().caller()),
                 config: None,
             }
         }
@@ -63,12 +73,6 @@ mod action_evm_transaction {
             version_tuple!()
         }
 
-        /// Gets the owner of the contract
-        #[ink(message)]
-        pub fn owner(&self) -> AccountId {
-            self.owner
-        }
-
         #[ink(message)]
         pub fn get_rpc(&self) -> Result<String> {
             let config = self.config.as_ref().ok_or(Error::NotConfigured)?;
@@ -147,11 +151,52 @@ mod action_evm_transaction {
 
         /// Returns BadOrigin error if the caller is not the owner
         fn ensure_owner(&self) -> Result<()> {
-            if self.env().caller() == self.owner {
-                Ok(())
-            } else {
-                Err(Error::BadOrigin)
-            }
+            self.ownership
+                .ensure_owner(&self.env().caller())
+                .or(Err(Error::BadOrigin))
+        }
+    }
+
+    impl Ownable for ActionEvmTransaction {
+        #[ink(message, selector = 0xfeaea4fa)]
+        fn owner(&self) -> AccountId {
+            self.ownership.owner_account()
+        }
+
+        #[ink(message)]
+        fn ownership_renounced(&self) -> bool {
+            self.ownership.is_renounced()
+        }
+
+        #[ink(message)]
+        fn pending_owner(&self) -> Option<AccountId> {
+            self.ownership.pending_owner()
+        }
+
+        #[ink(message)]
+        fn propose_owner(&mut self, new_owner: Option<AccountId>) -> ownership::Result<()> {
+            self.ownership
+                .propose_owner(&self.env().caller(), new_owner)
+        }
+
+        #[ink(message)]
+        fn accept_ownership(&mut self) -> ownership::Result<()> {
+            let transfer = self.ownership.accept_ownership(&self.env().caller())?;
+            self.env().emit_event(OwnershipTransferred {
+                previous_owner: transfer.previous_owner,
+                new_owner: transfer.new_owner,
+            });
+            Ok(())
+        }
+
+        #[ink(message)]
+        fn renounce_ownership(&mut self) -> ownership::Result<()> {
+            let transfer = self.ownership.renounce_ownership(&self.env().caller())?;
+            self.env().emit_event(OwnershipTransferred {
+                previous_owner: transfer.previous_owner,
+                new_owner: transfer.new_owner,
+            });
+            Ok(())
         }
     }
 
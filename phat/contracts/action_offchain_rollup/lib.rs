@@ -12,6 +12,7 @@ mod action_offchain_rollup {
     use ink::storage::traits::StorageLayout;
     use ink::storage::Lazy;
     use ink::ToAccountId;
+    use ownership::{Ownable, Ownership};
     use pink_extension::chain_extension::signing;
     use pink_web3::{
         api::{Eth, Namespace},
@@ -65,9 +66,18 @@ mod action_offchain_rollup {
         pub driver: JsDriver,
     }
 
+    /// Emitted when the ownership is accepted or renounced, `None` means no owner.
+    #[ink(event)]
+    pub struct OwnershipTransferred {
+        #[ink(topic)]
+        pub previous_owner: Option<AccountId>,
+        #[ink(topic)]
+        pub new_owner: Option<AccountId>,
+    }
+
     #[ink(storage)]
     pub struct ActionOffchainRollup {
-        owner: AccountId,
+        ownership: Ownership,
         /// Key for signing the rollup tx
         attest_key: [u8; 32],
         /// BrickProfile address to ask for tx signing (to pay gas fee)
@@ -138,7 +148,7 @@ mod action_offchain_rollup {
             const NONCE: &[u8] = b"attest_key";
             let random = signing::derive_sr25519_key(NONCE);
             Self {
-                owner: Self::env().caller(),
+                ownership: Ownership::new(Self::env().caller()),
                 attest_key: random[..32]
                     .try_into()
                     .expect("random is long enough; qed."),
@@ -174,15 +184,6 @@ mod action_offchain_rollup {
             version_tuple!()
         }
 
-        /// Gets the owner of the contract.
-        ///
-        /// @category Metadata
-        ///
-        #[ink(message)]
-        pub fn owner(&self) -> AccountId {
-            self.owner
-        }
-
         /// Get the identity of offchain rollup.
         ///
         /// @category Configuration
@@ -356,17 +357,17 @@ mod action_offchain_rollup {
             Ok(config)
         }
 
-        /// Transfers the ownership of the contract (only owner).
+        /// Proposes to transfer the ownership of the contract (only owner).
         ///
-        /// Transfer this to non-existent owner to renounce ownership and lock the configuration
+        /// Deprecated, use `propose_owner` instead. The new owner must accept the ownership with
+        /// `accept_ownership`, and `renounce_ownership` locks the configuration.
         ///
         /// @category Metadata
         ///
         #[ink(message)]
         pub fn transfer_ownership(&mut self, new_owner: AccountId) -> Result<()> {
-            self.ensure_owner()?;
-            self.owner = new_owner;
-            Ok(())
+            self.propose_owner(Some(new_owner))
+                .or(Err(Error::BadOrigin))
         }
 
         /// Pop an element from the rollup queue if any and process it, then submit the answer.
@@ -500,11 +501,9 @@ mod action_offchain_rollup {
 
         /// Returns BadOrigin error if the caller is not the owner.
         fn ensure_owner(&self) -> Result<()> {
-            if self.env().caller() == self.owner {
-                Ok(())
-            } else {
-                Err(Error::BadOrigin)
-            }
+            self.ownership
+                .ensure_owner(&self.env().caller())
+                .or(Err(Error::BadOrigin))
         }
 
         /// Returns the client config reference or raise the error `ClientNotConfigured`.
@@ -535,6 +534,61 @@ mod action_offchain_rollup {
         }
     }
 
+    impl Ownable for ActionOffchainRollup {
+        /// @category Metadata
+        ///
+        #[ink(message, selector = 0xfeaea4fa)]
+        fn owner(&self) -> AccountId {
+            self.ownership.owner_account()
+        }
+
+        /// @category Metadata
+        ///
+        #[ink(message)]
+        fn ownership_renounced(&self) -> bool {
+            self.ownership.is_renounced()
+        }
+
+        /// @category Metadata
+        ///
+        #[ink(message)]
+        fn pending_owner(&self) -> Option<AccountId> {
+            self.ownership.pending_owner()
+        }
+
+        /// @category Metadata
+        ///
+        #[ink(message)]
+        fn propose_owner(&mut self, new_owner: Option<AccountId>) -> ownership::Result<()> {
+            self.ownership
+                .propose_owner(&self.env().caller(), new_owner)
+        }
+
+        /// @category Metadata
+        ///
+        #[ink(message)]
+        fn accept_ownership(&mut self) -> ownership::Result<()> {
+            let transfer = self.ownership.accept_ownership(&self.env().caller())?;
+            self.env().emit_event(OwnershipTransferred {
+                previous_owner: transfer.previous_owner,
+                new_owner: transfer.new_owner,
+            });
+            Ok(())
+        }
+
+        /// @category Metadata
+        ///
+        #[ink(message)]
+        fn renounce_ownership(&mut self) -> ownership::Result<()> {
+            let transfer = self.ownership.renounce_ownership(&self.env().caller())?;
+            self.env().emit_event(OwnershipTransferred {
+                previous_owner: transfer.previous_owner,
+                new_owner: transfer.new_owner,
+            });
+            Ok(())
+        }
+    }
+
     fn connect(client: &Client) -> Result<EvmRollupClient> {
         let client_addr: H160 = client.client_addr.into();
         EvmRollupClient::new(&client.rpc, client_addr)
@@ -0,0 +1,125 @@
+//! A minimal matcher of 5-field cron expressions (minute hour day-of-month month day-of-week).
+//!
+//! Each field supports `*`, `n`, `a-b` and lists of them separated by `,`, with an optional
+//! `/step`. Both 0 and 7 are Sunday. Like Vixie cron, a day matches if either the day-of-month or
+//! the day-of-week matches when both of them are restricted. All times are in UTC.
+
+use alloc::{format, string::String, vec::Vec};
+use scale::{Decode, Encode};
+
+const MINUTE_MS: u64 = 60 * 1000;
+const DAY_MS: u64 = 24 * 60 * MINUTE_MS;
+
+/// The matching values of each field as bitsets, kept in the storage to match without parsing.
+#[derive(Encode, Decode, Debug)]
+#[cfg_attr(
+    feature = "std",
+    derive(scale_info::TypeInfo, ink::storage::traits::StorageLayout)
+)]
+pub struct CronExpr {
+    minutes: u64,
+    hours: u64,
+    days_of_month: u64,
+    months: u64,
+    days_of_week: u64,
+    any_day_of_month: bool,
+    any_day_of_week: bool,
+}
+
+impl CronExpr {
+    pub fn parse(expr: &str) -> Result<Self, String> {
+        let fields: Vec<&str> = expr.split_whitespace().collect();
+        let &[minute, hour, day_of_month, month, day_of_week] = fields.as_slice() else {
+            return Err(format!("expected 5 fields, got {}", fields.len()));
+        };
+        let mut days_of_week = parse_field(day_of_week, 0, 7)?;
+        if bit(days_of_week, 7) {
+            days_of_week |= 1;
+        }
+        Ok(Self {
+            minutes: parse_field(minute, 0, 59)?,
+            hours: parse_field(hour, 0, 23)?,
+            days_of_month: parse_field(day_of_month, 1, 31)?,
+            months: parse_field(month, 1, 12)?,
+            days_of_week,
+            any_day_of_month: day_of_month == "*",
+            any_day_of_week: day_of_week == "*",
+        })
+    }
+
+    /// Returns whether the minute of the unix timestamp (in milliseconds) matches.
+    pub fn matches(&self, timestamp: u64) -> bool {
+        let days = timestamp / DAY_MS;
+        let minute_of_day = timestamp % DAY_MS / MINUTE_MS;
+        let (month, day) = month_and_day(days);
+        // 1970-01-01 is a Thursday
+        let weekday = (days + 4) % 7;
+        let day_matches = match (self.any_day_of_month, self.any_day_of_week) {
+            (true, true) => true,
+            (false, true) => bit(self.days_of_month, day),
+            (true, false) => bit(self.days_of_week, weekday),
+            (false, false) => bit(self.days_of_month, day) || bit(self.days_of_week, weekday),
+        };
+        bit(self.minutes, minute_of_day % 60)
+            && bit(self.hours, minute_of_day / 60)
+            && bit(self.months, month)
+            && day_matches
+    }
+}
+
+/// Returns whether the two unix timestamps (in milliseconds) are in the same minute.
+pub fn same_minute(a: u64, b: u64) -> bool {
+    a / MINUTE_MS == b / MINUTE_MS
+}
+
+fn bit(set: u64, n: u64) -> bool {
+    set & (1 << n) != 0
+}
+
+fn parse_field(field: &str, min: u64, max: u64) -> Result<u64, String> {
+    let parse_number = |n: &str| {
+        n.parse::<u64>()
+            .map_err(|_| format!("invalid number {n:?} in {field:?}"))
+    };
+    let mut set = 0;
+    for part in field.split(',') {
+        let (range, step) = match part.split_once('/') {
+            Some((range, step)) => (range, parse_number(step)?),
+            None => (part, 1),
+        };
+        if step == 0 || step > max {
+            return Err(format!("step {step} is out of range in {field:?}"));
+        }
+        let (start, end) = match range.split_once('-') {
+            _ if range == "*" => (min, max),
+            Some((start, end)) => (parse_number(start)?, parse_number(end)?),
+            // `n/step` runs from n to the max
+            None if step > 1 => (parse_number(range)?, max),
+            None => {
+                let n = parse_number(range)?;
+                (n, n)
+            }
+        };
+        if start < min || end > max || start > end {
+            return Err(format!("{part:?} is out of range {min}-{max}"));
+        }
+        for n in (start..=end).step_by(step as usize) {
+            set |= 1 << n;
+        }
+    }
+    Ok(set)
+}
+
+/// Converts the days since 1970-01-01 to (month, day).
+///
+/// See http://howardhinnant.github.io/date_algorithms.html#civil_from_days
+fn month_and_day(days: u64) -> (u64, u64) {
+    let z = days + 719_468;
+    let doe = z % 146_097;
+    let yoe = (doe - doe / 1460 + doe / 36_524 - doe / 146_096) / 365;
+    let doy = doe - (365 * yoe + yoe / 4 - yoe / 100);
+    let mp = (5 * doy + 2) / 153;
+    let day = doy - (153 * mp + 2) / 5 + 1;
+    let month = if mp < 10 { mp + 3 } else { mp - 9 };
+    (month, day)
+}
@@ -0,0 +1,109 @@
+//! ECIES (SEC 1) over secp256k1, used to move secret keys between profiles.
+//!
+//! The shared secret is the x-coordinate of the ECDH point. It is derived into an encryption key
+//! and a MAC key with the ANSI X9.63 KDF over SHA-256. The 32-byte plaintext is XOR-ed with the
+//! encryption key and authenticated with HMAC-SHA256, so a ciphertext is laid out as
+//! `ephemeral public key (33 bytes, compressed) || encrypted (32 bytes) || tag (32 bytes)`.
+
+use alloc::vec::Vec;
+use ink::env::hash::{HashOutput, Sha2x256};
+use secp256k1::{ecdh, PublicKey, SecretKey};
+
+const PUBLIC_KEY_LEN: usize = 33;
+const KEY_LEN: usize = 32;
+pub const CIPHERTEXT_LEN: usize = PUBLIC_KEY_LEN + KEY_LEN + KEY_LEN;
+
+#[derive(Debug, PartialEq, Eq)]
+pub enum Error {
+    BadPublicKey,
+    BadSecretKey,
+    MalformedCiphertext,
+    TagMismatch,
+}
+
+/// Encrypts a 32-byte secret to the recipient public key.
+///
+/// The ephemeral key pair must be freshly generated for each encryption.
+pub fn encrypt(
+    recipient: &[u8],
+    ephemeral_secret: &[u8],
+    ephemeral_public: &[u8],
+    plaintext: &[u8; KEY_LEN],
+) -> Result<Vec<u8>, Error> {
+    if ephemeral_public.len() != PUBLIC_KEY_LEN {
+        return Err(Error::BadPublicKey);
+    }
+    let recipient = PublicKey::from_slice(recipient).or(Err(Error::BadPublicKey))?;
+    let ephemeral_secret = SecretKey::from_slice(ephemeral_secret).or(Err(Error::BadSecretKey))?;
+    let (enc_key, mac_key) = derive_keys(&recipient, &ephemeral_secret);
+
+    let mut encrypted = [0u8; KEY_LEN];
+    for (i, byte) in encrypted.iter_mut().enumerate() {
+        *byte = plaintext[i] ^ enc_key[i];
+    }
+    let tag = hmac_sha256(&mac_key, &encrypted);
+
+    let mut ciphertext = Vec::with_capacity(CIPHERTEXT_LEN);
+    ciphertext.extend_from_slice(ephemeral_public);
+    ciphertext.extend_from_slice(&encrypted);
+    ciphertext.extend_from_slice(&tag);
+    Ok(ciphertext)
+}
+
+/// Decrypts a 32-byte secret encrypted to the public key of `secret`.
+pub fn decrypt(secret: &[u8], ciphertext: &[u8]) -> Result<[u8; KEY_LEN], Error> {
+    if ciphertext.len() != CIPHERTEXT_LEN {
+        return Err(Error::MalformedCiphertext);
+    }
+    let (ephemeral_public, rest) = ciphertext.split_at(PUBLIC_KEY_LEN);
+    let (encrypted, tag) = rest.split_at(KEY_LEN);
+    let ephemeral_public =
+        PublicKey::from_slice(ephemeral_public).or(Err(Error::MalformedCiphertext))?;
+    let secret = SecretKey::from_slice(secret).or(Err(Error::BadSecretKey))?;
+    let (enc_key, mac_key) = derive_keys(&ephemeral_public, &secret);
+
+    let expected_tag = hmac_sha256(&mac_key, encrypted);
+    // Compare in constant time
+    let diff = expected_tag
+        .iter()
+        .zip(tag)
+        .fold(0u8, |diff, (a, b)| diff | (a ^ b));
+    if diff != 0 {
+        return Err(Error::TagMismatch);
+    }
+
+    let mut plaintext = [0u8; KEY_LEN];
+    for (i, byte) in plaintext.iter_mut().enumerate() {
+        *byte = encrypted[i] ^ enc_key[i];
+    }
+    Ok(plaintext)
+}
+
+/// Returns (encryption key, MAC key) derived from the ECDH shared secret.
+fn derive_keys(point: &PublicKey, scalar: &SecretKey) -> ([u8; KEY_LEN], [u8; KEY_LEN]) {
+    let shared_point = ecdh::shared_secret_point(point, scalar);
+    let z = &shared_point[..KEY_LEN];
+    (x963_kdf_block(z, 1), x963_kdf_block(z, 2))
+}
+
+fn x963_kdf_block(z: &[u8], counter: u32) -> [u8; KEY_LEN] {
+    sha256(&[z, &counter.to_be_bytes()].concat())
+}
+
+fn hmac_sha256(key: &[u8; KEY_LEN], message: &[u8]) -> [u8; KEY_LEN] {
+    const BLOCK_LEN: usize = 64;
+    let mut inner_pad = [0x36u8; BLOCK_LEN];
+    let mut outer_pad = [0x5cu8; BLOCK_LEN];
+    for (i, byte) in key.iter().enumerate() {
+        inner_pad[i] ^= byte;
+        outer_pad[i] ^= byte;
+    }
+    let inner = sha256(&[&inner_pad[..], message].concat());
+    sha256(&[&outer_pad[..], &inner[..]].concat())
+}
+
+fn sha256(input: &[u8]) -> [u8; KEY_LEN] {
+    let mut output = <Sha2x256 as HashOutput>::Type::default();
+    ink::env::hash_bytes::<Sha2x256>(input, &mut output);
+    output
+}
@@ -4,24 +4,32 @@ extern crate alloc;
 
 pub use brick_profile::*;
 
+mod cron;
+mod ecies;
+mod ss58;
+mod vault;
+
 #[ink::contract(env = pink::PinkEnvironment)]
 mod brick_profile {
-    use alloc::{format, string::String, vec::Vec};
+    use crate::{cron, ecies, ss58, vault};
+    use alloc::{format, string::String, vec, vec::Vec};
     use core::convert::TryInto;
+    use ink::env::hash::{Blake2x256, HashOutput, Sha2x256};
     #[cfg(feature = "std")]
     use ink::storage::traits::StorageLayout;
-    use ink::storage::{Lazy, Mapping};
+    use ink::storage::{traits::StorageKey, Lazy, Mapping};
     #[cfg(not(test))]
     use logging::info;
+    use ownership::{Ownable, Ownership};
     #[cfg(test)]
     use pink::info;
     use pink_extension as pink;
-    use pink_extension::chain_extension::signing;
+    use pink_extension::chain_extension::{signing, SigType};
     use pink_json as json;
     use pink_web3::{
         signing::Key,
         transports::{pink_http::PinkHttp, resolve_ready},
-        types::{TransactionParameters, TransactionRequest, H160},
+        types::{BlockNumber, TransactionParameters, TransactionRequest, H160, H256, U64},
     };
     use primitive_types::U256;
     use scale::{Decode, Encode};
@@ -30,17 +38,350 @@ mod brick_profile {
     pub type ExternalAccountId = u64;
     pub type WorkflowId = u64;
 
+    /// The role bound by `authorize_workflow` and used by the role-less session messages
+    pub const DEFAULT_ROLE: &str = "default";
+    const MAX_ROLE_LEN: usize = 32;
+    const MAX_POLL_ID_LEN: usize = 16;
+    const MAX_POLL_ERROR_LEN: usize = 256;
+    const EIP1559_TX_TYPE: u64 = 2;
+    const IMPORT_KEY_SALT: &[u8] = b"import_key";
+    const VAULT_KEY_SALT: &[u8] = b"secret_vault";
+    const MAX_SECRET_NAME_LEN: usize = 32;
+    const EXPORT_PROOF_CONTEXT: &[u8] = b"brick_profile:export";
+    const DAY_IN_MS: u64 = 24 * 60 * 60 * 1000;
+    /// Reserved nonces not seen by the rpc after this long are given up, e.g. the transaction
+    /// is dropped or never sent
+    const NONCE_RESERVATION_TTL_MS: u64 = 5 * 60 * 1000;
+    /// Failed rpc endpoints are tried last for this long
+    const RPC_COOLDOWN_MS: u64 = 60 * 1000;
+    /// Substrate signs the blake2_256 hash of the payload instead if it is longer than this
+    const MAX_UNHASHED_PAYLOAD_LEN: usize = 256;
+    /// The latest signed transactions kept for each account
+    const MAX_SIGNED_TX_RECORDS: usize = 64;
+    /// The version of the stored data, bump it with a new step in `migrate_from()` when the
+    /// encoding of the stored data changes. The root storage is never migrated, so it must keep
+    /// the packed fields of the first release and new states must be put in `Lazy` or `Mapping`
+    const STORAGE_VERSION: u32 = 1;
+
     #[derive(Encode, Decode, PartialEq, Debug)]
     #[cfg_attr(feature = "std", derive(scale_info::TypeInfo, StorageLayout))]
     pub enum ExternalAccountType {
         Imported,
         Generated,
         Dumped,
+        /// Exported to another profile, disabled as a dumped account
+        Exported {
+            to_profile: AccountId,
+        },
+        /// Imported from the key exported by another profile
+        Migrated {
+            from_profile: AccountId,
+            from_account: ExternalAccountId,
+        },
+    }
+
+    /// The key type of an external account.
+    #[derive(Encode, Decode, Clone, Copy, PartialEq, Debug)]
+    #[cfg_attr(feature = "std", derive(scale_info::TypeInfo, StorageLayout))]
+    pub enum KeyType {
+        /// A secp256k1 key of EVM chains
+        Evm,
+        /// A key of Substrate chains, the address is SS58 encoded with the network prefix
+        Substrate {
+            scheme: SubstrateScheme,
+            ss58_prefix: u16,
+        },
+    }
+
+    /// The roles the owner can grant to other accounts to share the profile.
+    #[derive(Encode, Decode, Clone, Copy, PartialEq, Debug)]
+    #[cfg_attr(feature = "std", derive(scale_info::TypeInfo, StorageLayout))]
+    pub enum OperatorRole {
+        Viewer,
+        WorkflowEditor,
+        AccountOperator,
+    }
+
+    /// The permissions checked by the admin messages, the owner has all of them.
+    ///
+    /// Key export and dump, the js_runner config, the poll reporter and the operators themselves
+    /// are only managed by the owner.
+    #[derive(Encode, Decode, Clone, Copy, PartialEq, Debug)]
+    #[cfg_attr(feature = "std", derive(scale_info::TypeInfo))]
+    pub enum Permission {
+        /// Read the rpc endpoints and chain info of the accounts
+        ViewAccounts,
+        /// Add, update, remove and schedule workflows
+        EditWorkflows,
+        /// Enable and disable workflows
+        PauseWorkflows,
+        /// Generate accounts and change their rpc, chain id and low watermark
+        ManageAccounts,
+        /// Bind accounts to workflows and set the signing policies
+        AuthorizeWorkflows,
+    }
+
+    impl OperatorRole {
+        pub fn permissions(self) -> &'static [Permission] {
+            match self {
+                OperatorRole::Viewer => &[Permission::ViewAccounts],
+                OperatorRole::WorkflowEditor => &[
+                    Permission::ViewAccounts,
+                    Permission::EditWorkflows,
+                    Permission::PauseWorkflows,
+                ],
+                OperatorRole::AccountOperator => &[
+                    Permission::ViewAccounts,
+                    Permission::PauseWorkflows,
+                    Permission::ManageAccounts,
+                    Permission::AuthorizeWorkflows,
+                ],
+            }
+        }
+    }
+
+    /// Emitted when the owner grants an operator role.
+    #[ink(event)]
+    pub struct OperatorRoleGranted {
+        #[ink(topic)]
+        pub operator: AccountId,
+        pub role: OperatorRole,
+    }
+
+    /// Emitted when the owner revokes an operator role.
+    #[ink(event)]
+    pub struct OperatorRoleRevoked {
+        #[ink(topic)]
+        pub operator: AccountId,
+        pub role: OperatorRole,
+    }
+
+    /// Emitted when a secret is written, the value is never emitted.
+    #[ink(event)]
+    pub struct SecretSet {
+        pub name: String,
+    }
+
+    #[ink(event)]
+    pub struct SecretRemoved {
+        pub name: String,
+    }
+
+    #[ink(event)]
+    pub struct SecretGrantChanged {
+        pub name: String,
+        #[ink(topic)]
+        pub workflow_id: WorkflowId,
+        pub granted: bool,
+    }
+
+    #[derive(Encode, Decode, Clone, Copy, PartialEq, Debug)]
+    #[cfg_attr(feature = "std", derive(scale_info::TypeInfo, StorageLayout))]
+    pub enum SubstrateScheme {
+        Sr25519,
+        Ed25519,
+    }
+
+    impl SubstrateScheme {
+        fn sig_type(self) -> SigType {
+            match self {
+                SubstrateScheme::Sr25519 => SigType::Sr25519,
+                SubstrateScheme::Ed25519 => SigType::Ed25519,
+            }
+        }
+    }
+
+    /// An EVM secret key exported for another profile.
+    #[derive(Encode, Decode, Debug, Clone, PartialEq)]
+    #[cfg_attr(feature = "std", derive(scale_info::TypeInfo))]
+    pub struct ExportedKey {
+        pub from_profile: AccountId,
+        pub from_account: ExternalAccountId,
+        pub to_profile: AccountId,
+        /// The secret key encrypted to the import key of `to_profile` with ECIES over secp256k1
+        pub ciphertext: Vec<u8>,
+        /// The ECDSA signature by the exported key over the fields above
+        pub proof: Vec<u8>,
+    }
+
+    impl ExportedKey {
+        fn proof_message(&self) -> [u8; 32] {
+            let mut hash = <Sha2x256 as HashOutput>::Type::default();
+            ink::env::hash_encoded::<Sha2x256, _>(
+                &(
+                    EXPORT_PROOF_CONTEXT,
+                    self.from_profile,
+                    self.from_account,
+                    self.to_profile,
+                    &self.ciphertext,
+                ),
+                &mut hash,
+            );
+            hash
+        }
+    }
+
+    /// Emitted when the js_runner is configured.
+    #[ink(event)]
+    pub struct JsRunnerConfigured {
+        pub js_runner: AccountId,
+    }
+
+    #[ink(event)]
+    pub struct WorkflowAdded {
+        #[ink(topic)]
+        pub workflow_id: WorkflowId,
+        pub name: String,
+    }
+
+    /// Emitted when the name or commandline of a workflow is changed.
+    #[ink(event)]
+    pub struct WorkflowUpdated {
+        #[ink(topic)]
+        pub workflow_id: WorkflowId,
+    }
+
+    #[ink(event)]
+    pub struct WorkflowRemoved {
+        #[ink(topic)]
+        pub workflow_id: WorkflowId,
+    }
+
+    #[ink(event)]
+    pub struct WorkflowEnabled {
+        #[ink(topic)]
+        pub workflow_id: WorkflowId,
+    }
+
+    #[ink(event)]
+    pub struct WorkflowDisabled {
+        #[ink(topic)]
+        pub workflow_id: WorkflowId,
+    }
+
+    #[ink(event)]
+    pub struct WorkflowScheduleChanged {
+        #[ink(topic)]
+        pub workflow_id: WorkflowId,
+        pub schedule: Option<Schedule>,
+    }
+
+    #[ink(event)]
+    pub struct WorkflowRunnerChanged {
+        #[ink(topic)]
+        pub workflow_id: WorkflowId,
+        pub runner: Option<WorkflowRunner>,
+    }
+
+    /// Emitted when a workflow is granted or revoked the account of a role, `None` means revoked.
+    #[ink(event)]
+    pub struct WorkflowAuthorizationChanged {
+        #[ink(topic)]
+        pub workflow_id: WorkflowId,
+        #[ink(topic)]
+        pub account_id: Option<ExternalAccountId>,
+        pub role: String,
+    }
+
+    /// Emitted when the signing policy is set or removed, `None` means removed.
+    #[ink(event)]
+    pub struct SigningPolicyChanged {
+        #[ink(topic)]
+        pub workflow_id: WorkflowId,
+        #[ink(topic)]
+        pub account_id: ExternalAccountId,
+        pub policy: Option<SigningPolicy>,
+    }
+
+    #[ink(event)]
+    pub struct PollReported {
+        #[ink(topic)]
+        pub workflow_id: WorkflowId,
+        pub poll_id: String,
+        pub succeeded: bool,
+    }
+
+    #[ink(event)]
+    pub struct PollReporterChanged {
+        pub reporter: Option<AccountId>,
+    }
+
+    /// Emitted when an account is generated or imported.
+    #[ink(event)]
+    pub struct ExternalAccountCreated {
+        #[ink(topic)]
+        pub account_id: ExternalAccountId,
+        pub account_type: ExternalAccountType,
+        pub key_type: KeyType,
+    }
+
+    /// Emitted when an account is dumped or exported, the account is disabled then.
+    #[ink(event)]
+    pub struct ExternalAccountDisabled {
+        #[ink(topic)]
+        pub account_id: ExternalAccountId,
+        pub account_type: ExternalAccountType,
+    }
+
+    #[ink(event)]
+    pub struct RpcEndpointsChanged {
+        #[ink(topic)]
+        pub account_id: ExternalAccountId,
+        pub rpcs: Vec<String>,
+    }
+
+    #[ink(event)]
+    pub struct RpcQuorumChanged {
+        #[ink(topic)]
+        pub account_id: ExternalAccountId,
+        pub enabled: bool,
+    }
+
+    #[ink(event)]
+    pub struct ChainIdChanged {
+        #[ink(topic)]
+        pub account_id: ExternalAccountId,
+        pub chain_id: Option<u64>,
+    }
+
+    #[ink(event)]
+    pub struct LowWatermarkChanged {
+        #[ink(topic)]
+        pub account_id: ExternalAccountId,
+        pub low_watermark: Option<u128>,
+    }
+
+    /// Emitted when the ownership is accepted or renounced, `None` means no owner.
+    #[ink(event)]
+    pub struct OwnershipTransferred {
+        #[ink(topic)]
+        pub previous_owner: Option<AccountId>,
+        #[ink(topic)]
+        pub new_owner: Option<AccountId>,
+    }
+
+    /// Emitted when the contract code is upgraded.
+    #[ink(event)]
+    pub struct Upgraded {
+        pub code_hash: Hash,
+    }
+
+    /// Emitted when the stored data is migrated to the storage version of the code.
+    #[ink(event)]
+    pub struct StorageMigrated {
+        pub from_version: u32,
+        pub to_version: u32,
+    }
+
+    /// Emitted when the account allowed to upgrade the contract besides the owner is changed.
+    #[ink(event)]
+    pub struct UpgraderChanged {
+        pub upgrader: Option<AccountId>,
     }
 
     #[ink(storage)]
     pub struct BrickProfile {
-        owner: AccountId,
+        ownership: Ownership,
         config: Option<Config>,
         next_workflow_id: WorkflowId,
         workflows: Mapping<WorkflowId, Workflow>,
@@ -48,6 +389,35 @@ mod brick_profile {
         external_accounts: Mapping<ExternalAccountId, ExternalAccount>,
         authorized_account: Mapping<WorkflowId, ExternalAccountId>,
         workflow_session: Lazy<WorkflowId>,
+        signing_policies: Mapping<(WorkflowId, ExternalAccountId), SigningPolicy>,
+        /// The accounts bound to the named roles other than the default one
+        role_accounts: Mapping<(WorkflowId, String), ExternalAccountId>,
+        workflow_roles: Mapping<WorkflowId, Vec<String>>,
+        /// Tombstones of the removed workflows with the removal time, ids are never reused
+        removed_workflows: Mapping<WorkflowId, Timestamp>,
+        /// The account allowed to report poll results besides the owner, e.g. the scheduler
+        poll_reporter: Lazy<Option<AccountId>>,
+        workflow_status: Mapping<WorkflowId, WorkflowStatus>,
+        workflow_schedules: Mapping<WorkflowId, Schedule>,
+        /// The parsed cron expressions of the schedules
+        workflow_crons: Mapping<WorkflowId, cron::CronExpr>,
+        /// The workflows with schedules, scanned by `due_workflows`
+        scheduled_workflows: Lazy<Vec<WorkflowId>>,
+        /// The per-workflow overrides of the js_runner
+        workflow_runners: Mapping<WorkflowId, WorkflowRunner>,
+        /// The native balance below which an account is reported as low on funds
+        low_watermarks: Mapping<ExternalAccountId, u128>,
+        /// The accounts holding operator roles
+        operators: Lazy<Vec<AccountId>>,
+        operator_roles: Mapping<AccountId, Vec<OperatorRole>>,
+        /// Missing in the profiles created before the storage is versioned, i.e. version 0
+        storage_version: Lazy<u32>,
+        secret_names: Lazy<Vec<String>>,
+        secrets: Mapping<String, Secret>,
+        /// The account allowed to upgrade the contract besides the owner, e.g. the factory
+        upgrader: Lazy<Option<AccountId>>,
+        /// The accounts imported from other profiles, so an exported key is imported only once
+        imported_accounts: Mapping<(AccountId, ExternalAccountId), ExternalAccountId>,
     }
 
     #[derive(Encode, Decode, Debug)]
@@ -65,6 +435,22 @@ mod brick_profile {
         account_type: ExternalAccountType,
         // This determines on which chain you can use this account
         // The same sk can be used to create multiple ExternalAccounts on different chains
+        /// The rpc endpoints of the same chain in the order of preference, never empty
+        rpcs: Vec<String>,
+        sk: [u8; 32],
+        /// The cached EIP-155 chain id of `rpcs`, fetched from the rpc on demand if not set
+        chain_id: Option<u64>,
+        key_type: KeyType,
+        /// Whether the chain id and block number must be agreed by two rpc endpoints
+        rpc_quorum: bool,
+    }
+
+    /// The `ExternalAccount` stored before the storage is versioned, i.e. version 0.
+    #[derive(Decode, Debug)]
+    struct LegacyExternalAccount {
+        id: ExternalAccountId,
+        enabled: bool,
+        account_type: ExternalAccountType,
         rpc: String,
         sk: [u8; 32],
     }
@@ -86,6 +472,66 @@ mod brick_profile {
         enabled: bool,
         commandline: String,
         authorized_account: Option<ExternalAccountId>,
+        role_accounts: Vec<(String, ExternalAccountId)>,
+        schedule: Option<Schedule>,
+        /// Overrides the js_runner of the profile if set
+        runner: Option<WorkflowRunner>,
+    }
+
+    /// The contract message a workflow is dispatched to instead of the `run` of the js_runner.
+    #[derive(Encode, Decode, Debug, Clone, PartialEq)]
+    #[cfg_attr(feature = "std", derive(scale_info::TypeInfo, StorageLayout))]
+    pub struct WorkflowRunner {
+        pub contract: AccountId,
+        pub selector: [u8; 4],
+        /// Whether the commandline is passed as the only `String` argument, the message takes no
+        /// argument otherwise
+        pub with_commandline: bool,
+        pub output: RunnerOutput,
+    }
+
+    /// The return type of a runner message, which tells whether the run succeeded.
+    #[derive(Encode, Decode, Debug, Clone, Copy, PartialEq)]
+    #[cfg_attr(feature = "std", derive(scale_info::TypeInfo, StorageLayout))]
+    pub enum RunnerOutput {
+        /// `bool`, e.g. `run` of the js_runner
+        Bool,
+        /// `Result<Option<Vec<u8>>, E>` with any `E`, succeeded if `Ok`, e.g. `answer_request` of
+        /// action_offchain_rollup
+        ResultOptionBytes,
+    }
+
+    #[derive(Encode, Decode, Debug, Clone, PartialEq)]
+    #[cfg_attr(feature = "std", derive(scale_info::TypeInfo, StorageLayout))]
+    pub enum Trigger {
+        /// Polls every given milliseconds
+        Interval(u64),
+        /// Polls at the minutes matching a 5-field cron expression in UTC
+        Cron(String),
+    }
+
+    /// When a workflow should be polled, the scheduler reads it with `due_workflows`.
+    #[derive(Encode, Decode, Debug, Clone, PartialEq)]
+    #[cfg_attr(feature = "std", derive(scale_info::TypeInfo, StorageLayout))]
+    pub struct Schedule {
+        pub trigger: Trigger,
+        /// The workflow is not due before this time if set
+        pub start_at: Option<Timestamp>,
+        /// The workflow is not due from this time on if set
+        pub end_at: Option<Timestamp>,
+    }
+
+    /// The poll history of a workflow, reported by the scheduler after each poll.
+    #[derive(Encode, Decode, Debug, Default, Clone, PartialEq)]
+    #[cfg_attr(feature = "std", derive(scale_info::TypeInfo, StorageLayout))]
+    pub struct WorkflowStatus {
+        pub last_poll_id: Option<String>,
+        /// The time the last poll is reported
+        pub last_poll_at: Option<Timestamp>,
+        pub last_success_at: Option<Timestamp>,
+        pub consecutive_failures: u32,
+        /// The error of the last failed poll, truncated to 256 bytes
+        pub last_error: Option<String>,
     }
 
     #[derive(Encode, Decode, Debug)]
@@ -93,7 +539,169 @@ mod brick_profile {
     pub struct ExternalAccountInfo {
         id: ExternalAccountId,
         address: H160,
-        rpc: String,
+        rpcs: Vec<String>,
+        chain_id: Option<u64>,
+    }
+
+    #[derive(Encode, Decode, Debug)]
+    #[cfg_attr(feature = "std", derive(scale_info::TypeInfo))]
+    pub struct AccountBalance {
+        id: ExternalAccountId,
+        address: H160,
+        /// The native balance in wei, `None` if it cannot be read from the rpc
+        balance: Option<U256>,
+        low_watermark: Option<u128>,
+    }
+
+    /// A transaction signed by `sign_evm_transaction`, it may never be sent by the workflow.
+    #[derive(Encode, Decode, Debug, Clone, PartialEq)]
+    #[cfg_attr(feature = "std", derive(scale_info::TypeInfo))]
+    pub struct SignedTransaction {
+        pub hash: H256,
+        pub workflow_id: WorkflowId,
+        pub nonce: U256,
+        pub to: Option<H160>,
+        pub value: U256,
+        pub gas_limit: U256,
+        pub signed_at: Timestamp,
+    }
+
+    impl AccountBalance {
+        /// Returns whether the balance is known to be below the low watermark.
+        fn is_low(&self) -> bool {
+            match (self.balance, self.low_watermark) {
+                (Some(balance), Some(low_watermark)) => balance < U256::from(low_watermark),
+                _ => false,
+            }
+        }
+    }
+
+    /// A secret of the vault, encrypted with the vault key.
+    #[derive(Encode, Decode, Debug)]
+    #[cfg_attr(feature = "std", derive(scale_info::TypeInfo, StorageLayout))]
+    struct Secret {
+        nonce: [u8; vault::NONCE_LEN],
+        ciphertext: Vec<u8>,
+        /// The workflows allowed to read the secret
+        workflows: Vec<WorkflowId>,
+    }
+
+    #[derive(Encode, Decode, Debug, PartialEq)]
+    #[cfg_attr(feature = "std", derive(scale_info::TypeInfo))]
+    pub struct SecretInfo {
+        pub name: String,
+        pub workflows: Vec<WorkflowId>,
+    }
+
+    #[derive(Encode, Decode, Debug)]
+    #[cfg_attr(feature = "std", derive(scale_info::TypeInfo))]
+    pub struct SubstrateAccountInfo {
+        id: ExternalAccountId,
+        scheme: SubstrateScheme,
+        public_key: Vec<u8>,
+        /// The SS58 address
+        address: String,
+        rpcs: Vec<String>,
+    }
+
+    /// Restrictions on the transactions a workflow can sign with an external account.
+    ///
+    /// `None` means no restriction on that field.
+    #[derive(Encode, Decode, Debug, Default, Clone, PartialEq)]
+    #[cfg_attr(feature = "std", derive(scale_info::TypeInfo, StorageLayout))]
+    pub struct SigningPolicy {
+        /// The allowed `to` addresses, contract creation is not allowed once set
+        pub allowed_to: Option<Vec<[u8; 20]>>,
+        /// The allowed function selectors, calls without a selector are not allowed once set
+        pub allowed_selectors: Option<Vec<[u8; 4]>>,
+        /// The max `value` of a single transaction in wei
+        pub max_value: Option<u128>,
+        /// The max gas price (or max fee per gas) in wei
+        pub max_gas_price: Option<u128>,
+        /// The max total of `value` and gas fee in wei signed in the last 24 hours
+        ///
+        /// The spending is tracked in the worker local cache, so this is a best-effort limit.
+        pub daily_spend_limit: Option<u128>,
+    }
+
+    impl SigningPolicy {
+        /// Checks the transaction against the policy and returns the max amount it can spend.
+        fn check(&self, tx: &TransactionParameters) -> Result<U256> {
+            if let Some(allowed_to) = &self.allowed_to {
+                let Some(to) = tx.to else {
+                    return Err(Error::PolicyViolation(
+                        "contract creation is not allowed".into(),
+                    ));
+                };
+                if !allowed_to.contains(&to.0) {
+                    return Err(Error::PolicyViolation(format!(
+                        "to address {to:?} is not allowed"
+                    )));
+                }
+            }
+            if let Some(allowed_selectors) = &self.allowed_selectors {
+                let selector: [u8; 4] = tx
+                    .data
+                    .0
+                    .get(..4)
+                    .and_then(|selector| selector.try_into().ok())
+                    .ok_or_else(|| Error::PolicyViolation("no function selector".into()))?;
+                if !allowed_selectors.contains(&selector) {
+                    return Err(Error::PolicyViolation(format!(
+                        "function selector 0x{} is not allowed",
+                        hex_fmt::HexFmt(selector)
+                    )));
+                }
+            }
+            if let Some(max_value) = self.max_value {
+                if tx.value > U256::from(max_value) {
+                    return Err(Error::PolicyViolation(format!(
+                        "value {} exceeds the cap {max_value}",
+                        tx.value
+                    )));
+                }
+            }
+            let gas_price = tx.max_fee_per_gas.or(tx.gas_price);
+            if let Some(max_gas_price) = self.max_gas_price {
+                // Otherwise the gas price is decided by the rpc
+                let Some(gas_price) = gas_price else {
+                    return Err(Error::PolicyViolation("gas price is required".into()));
+                };
+                if gas_price > U256::from(max_gas_price) {
+                    return Err(Error::PolicyViolation(format!(
+                        "gas price {gas_price} exceeds the cap {max_gas_price}"
+                    )));
+                }
+            }
+            let gas_fee = gas_price.unwrap_or_default().saturating_mul(tx.gas);
+            Ok(tx.value.saturating_add(gas_fee))
+        }
+    }
+
+    /// The actions accepted by the js_runner, only used to validate commandlines. `call` and `log`
+    /// are run by lego_rs, the configs of the `fetch` and `eval` of the JS runner are left to it.
+    #[derive(serde::Deserialize)]
+    #[serde(tag = "cmd", content = "config")]
+    #[serde(rename_all = "camelCase")]
+    enum LegoAction {
+        Call { callee: String, selector: u32 },
+        Log,
+        Fetch(serde::de::IgnoredAny),
+        Eval(serde::de::IgnoredAny),
+    }
+
+    /// The action shape passed to the runner overrides, only used to validate commandlines. The
+    /// optional `config` is up to the runner.
+    #[derive(serde::Deserialize)]
+    struct RunnerAction {
+        cmd: String,
+    }
+
+    /// The unsigned transaction fields that are not covered by `TransactionRequest`.
+    #[derive(serde::Deserialize, Default)]
+    #[serde(rename_all = "camelCase")]
+    struct TransactionExtras {
+        chain_id: Option<U64>,
     }
 
     #[derive(Encode, Decode, Debug)]
@@ -108,22 +716,54 @@ mod brick_profile {
         BadUnsignedTransaction,
         WorkflowNotFound,
         WorkflowDisabled,
+        WorkflowRemoved,
         NoAuthorizedExternalAccount,
         ExternalAccountNotFound,
         ExternalAccountDisabled,
         FailedToGetEthAccounts(String),
         FailedToSignTransaction(String),
         OnlyDumpedAccount,
+        OnlyExportedAccount,
+        BadPublicKey,
+        BadExportedKey,
+        NotEvmAccount,
+        NotSubstrateAccount,
+        InvalidSs58Prefix,
         InvalidPollId,
         FailedToReadChainId(String),
+        FailedToReadNonce(String),
+        FailedToReadBlockNumber(String),
+        NoRpcEndpoint,
+        FailedToCallRunner(String),
+        ChainIdMismatch,
+        PolicyViolation(String),
+        InvalidRole,
+        /// The commandline is rejected by the action schema of the runner, `index` points at the
+        /// offending action if the commandline parses
+        InvalidCommandline {
+            index: Option<u32>,
+            reason: String,
+        },
+        InvalidSchedule(String),
+        FailedToUpgrade(String),
+        /// The stored data is written by a newer code than the running one
+        StorageVersionTooNew(u32),
+        InvalidSecretName,
+        SecretNotFound,
+        SecretNotGranted,
+        NonceOverflow,
+        BadSubstrateSecretKey,
+        BadImportKey,
+        AccountAlreadyImported,
+        RoleNotBound,
     }
     pub type Result<T> = core::result::Result<T, Error>;
 
     impl BrickProfile {
         #[ink(constructor)]
         pub fn new(owner: AccountId) -> Self {
-            Self {
-                owner,
+            let mut profile = Self {
+                ownership: Ownership::new(owner),
                 config: None,
                 next_workflow_id: 0,
                 workflows: Mapping::default(),
@@ -131,7 +771,27 @@ mod brick_profile {
                 external_accounts: Mapping::default(),
                 authorized_account: Mapping::default(),
                 workflow_session: Default::default(),
-            }
+                signing_policies: Mapping::default(),
+                role_accounts: Mapping::default(),
+                workflow_roles: Mapping::default(),
+                removed_workflows: Mapping::default(),
+                poll_reporter: Default::default(),
+                workflow_status: Mapping::default(),
+                workflow_schedules: Mapping::default(),
+                workflow_crons: Mapping::default(),
+                scheduled_workflows: Default::default(),
+                workflow_runners: Mapping::default(),
+                low_watermarks: Mapping::default(),
+                operators: Default::default(),
+                operator_roles: Mapping::default(),
+                storage_version: Default::default(),
+                secret_names: Default::default(),
+                secrets: Mapping::default(),
+                upgrader: Default::default(),
+                imported_accounts: Mapping::default(),
+            };
+            profile.storage_version.set(&STORAGE_VERSION);
+            profile
         }
 
         #[ink(constructor)]
@@ -145,13 +805,111 @@ mod brick_profile {
             version_tuple!()
         }
 
-        /// Gets the owner of the contract.
+        /// Gets the code hash the contract is running.
+        ///
+        /// @category Metadata
+        ///
+        #[ink(message)]
+        pub fn code_hash(&self) -> Hash {
+            self.env()
+                .own_code_hash()
+                .expect("the contract is running; qed.")
+        }
+
+        /// Gets the version of the stored data.
+        ///
+        /// @category Metadata
+        ///
+        #[ink(message)]
+        pub fn storage_version(&self) -> u32 {
+            self.storage_version.get().unwrap_or_default()
+        }
+
+        /// Gets the account allowed to upgrade the contract besides the owner.
+        ///
+        /// @category Metadata
+        ///
+        #[ink(message)]
+        pub fn get_upgrader(&self) -> Option<AccountId> {
+            self.upgrader.get().flatten()
+        }
+
+        /// Allows an account to upgrade the contract, e.g. opts in to the upgrades rolled out by
+        /// the factory (only owner).
+        ///
+        /// Pass `None` to opt out.
+        ///
+        /// @category Metadata
+        ///
+        #[ink(message)]
+        pub fn set_upgrader(&mut self, upgrader: Option<AccountId>) -> Result<()> {
+            self.ensure_owner()?;
+            self.upgrader.set(&upgrader);
+            self.env().emit_event(UpgraderChanged { upgrader });
+            Ok(())
+        }
+
+        /// Upgrades the contract code and migrates the stored data with the new code (only owner
+        /// or the upgrader).
+        ///
+        /// The migrations run by calling `migrate()` of the new code in the same transaction, so
+        /// the upgrade is reverted if they fail.
+        ///
+        /// @category Metadata
+        ///
+        #[ink(message)]
+        pub fn upgrade(&mut self, code_hash: Hash) -> Result<()> {
+            use ink::env::call::{build_call, ExecutionInput, Selector};
+
+            let caller = self.env().caller();
+            if !self.ownership.is_owner(&caller) && self.get_upgrader() != Some(caller) {
+                return Err(Error::BadOrigin);
+            }
+            self.env()
+                .set_code_hash(&code_hash)
+                .map_err(|err| Error::FailedToUpgrade(format!("{:?}", err)))?;
+            let migrated = build_call::<pink::PinkEnvironment>()
+                .call(self.env().account_id())
+                .transferred_value(0)
+                .call_flags(ink::env::CallFlags::default().set_allow_reentry(true))
+                .exec_input(ExecutionInput::new(Selector::new(ink::selector_bytes!(
+                    "migrate"
+                ))))
+                .returns::<Result<()>>()
+                .try_invoke()
+                .map_err(|err| Error::FailedToUpgrade(format!("{:?}", err)))?
+                .map_err(|err| Error::FailedToUpgrade(format!("{:?}", err)))?;
+            migrated?;
+            self.env().emit_event(Upgraded { code_hash });
+            Ok(())
+        }
+
+        /// Migrates the stored data to the storage version of the running code.
+        ///
+        /// It is called by `upgrade()`, and can be called by anyone since it does nothing once the
+        /// data is up to date.
         ///
         /// @category Metadata
         ///
         #[ink(message)]
-        pub fn owner(&self) -> AccountId {
-            self.owner
+        pub fn migrate(&mut self) -> Result<()> {
+            let from_version = self.storage_version();
+            if from_version > STORAGE_VERSION {
+                return Err(Error::StorageVersionTooNew(from_version));
+            }
+            if from_version == STORAGE_VERSION {
+                return Ok(());
+            }
+            for version in from_version..STORAGE_VERSION {
+                self.migrate_from(version);
+            }
+            self.storage_version.set(&STORAGE_VERSION);
+            info!("storage migrated from version {from_version} to {STORAGE_VERSION}");
+            self.env().emit_event(StorageMigrated {
+                from_version,
+                to_version: STORAGE_VERSION,
+            });
+            Ok(())
         }
 
         /// Gets the contract address of Js runner contract.
@@ -172,11 +930,14 @@ mod brick_profile {
         pub fn config(&mut self, js_runner: AccountId) -> Result<()> {
             self.ensure_owner()?;
             self.config = Some(Config { js_runner });
+            self.env().emit_event(JsRunnerConfigured { js_runner });
             Ok(())
         }
 
         /// Gets the total number of workerflows.
         ///
+        /// The removed workflows are counted since their ids are never reused.
+        ///
         /// @category Workflow
         ///
         #[ink(message)]
@@ -201,29 +962,62 @@ mod brick_profile {
             counts
         }
 
-        /// Adds a new workflow (only owner).
+        /// Adds a new workflow (requires `EditWorkflows`).
+        ///
+        /// The commandline must be a JSON array of the actions accepted by the js_runner, i.e. the
+        /// `call` and `log` of lego_rs or the `fetch` and `eval` of the JS runner.
         ///
         /// @category Workflow
         ///
         #[ink(message)]
         pub fn add_workflow(&mut self, name: String, commandline: String) -> Result<WorkflowId> {
-            self.ensure_owner()?;
+            self.add_workflow_with_runner(name, commandline, None)
+        }
+
+        /// Adds a new workflow with a runner override, see `set_workflow_runner` (requires
+        /// `EditWorkflows`).
+        ///
+        /// A runner override taking the commandline only requires a JSON array of `{"cmd": ...,
+        /// "config": ...}` actions.
+        ///
+        /// @category Workflow
+        ///
+        #[ink(message)]
+        pub fn add_workflow_with_runner(
+            &mut self,
+            name: String,
+            commandline: String,
+            runner: Option<WorkflowRunner>,
+        ) -> Result<WorkflowId> {
+            self.ensure_permission(Permission::EditWorkflows)?;
 
             let id = self.next_workflow_id;
-            // TODO: validate commandline?
+            validate_commandline(&commandline, runner.as_ref())?;
             let workflow = Workflow {
                 id,
-                name,
+                name: name.clone(),
                 enabled: true,
                 commandline,
             };
             self.workflows.insert(id, &workflow);
             self.next_workflow_id += 1;
+            self.env().emit_event(WorkflowAdded {
+                workflow_id: id,
+                name,
+            });
+            if let Some(runner) = runner {
+                self.workflow_runners.insert(id, &runner);
+                self.env().emit_event(WorkflowRunnerChanged {
+                    workflow_id: id,
+                    runner: Some(runner),
+                });
+            }
 
             Ok(id)
         }
 
-        /// Adds a new workflow and authorizes it to use the account (only owner).
+        /// Adds a new workflow and authorizes it to use the account (requires `EditWorkflows` and
+        /// `AuthorizeWorkflows`).
         ///
         /// @category Workflow
         #[ink(message)]
@@ -233,7 +1027,8 @@ mod brick_profile {
             commandline: String,
             account: ExternalAccountId,
         ) -> Result<WorkflowId> {
-            self.ensure_owner()?;
+            self.ensure_permission(Permission::EditWorkflows)?;
+            self.ensure_permission(Permission::AuthorizeWorkflows)?;
 
             let id = self.next_workflow_id;
             self.add_workflow(name, commandline)?;
@@ -248,17 +1043,13 @@ mod brick_profile {
         #[ink(message)]
         pub fn get_workflow(&self, id: WorkflowId) -> Result<WorkflowInfo> {
             let workflow = self.ensure_workflow(id)?;
-            Ok(WorkflowInfo {
-                id: workflow.id,
-                name: workflow.name,
-                enabled: workflow.enabled,
-                commandline: workflow.commandline,
-                authorized_account: self.authorized_account.get(id),
-            })
+            Ok(self.workflow_info(workflow))
         }
 
         /// Gets all workflows.
         ///
+        /// Use `get_workflows` to page through the workflows of a large profile.
+        ///
         /// @category Workflow
         ///
         #[ink(message)]
@@ -266,60 +1057,361 @@ mod brick_profile {
             let mut workflows = Vec::new();
             for id in 0..self.next_workflow_id {
                 if let Some(workflow) = self.workflows.get(id) {
-                    workflows.push(WorkflowInfo {
-                        id: workflow.id,
-                        name: workflow.name.clone(),
-                        enabled: workflow.enabled,
-                        commandline: workflow.commandline.clone(),
-                        authorized_account: self.authorized_account.get(id),
-                    });
+                    workflows.push(self.workflow_info(workflow));
                 }
             }
             Ok(workflows)
         }
 
-        /// Enable a workflow (only owner).
+        /// Gets at most `limit` workflows with ids starting from `offset`.
+        ///
+        /// The removed workflows are skipped, so use the last returned id + 1 as the next offset.
         ///
         /// @category Workflow
         ///
         #[ink(message)]
-        pub fn enable_workflow(&mut self, id: WorkflowId) -> Result<()> {
-            self.ensure_owner()?;
-            let mut workflow = self.ensure_workflow(id)?;
-            if !workflow.enabled {
-                workflow.enabled = true;
-                self.workflows.insert(id, &workflow);
+        pub fn get_workflows(&self, offset: WorkflowId, limit: u64) -> Result<Vec<WorkflowInfo>> {
+            let mut workflows = Vec::new();
+            for id in offset..self.next_workflow_id {
+                if workflows.len() as u64 >= limit {
+                    break;
+                }
+                if let Some(workflow) = self.workflows.get(id) {
+                    workflows.push(self.workflow_info(workflow));
+                }
             }
-            Ok(())
+            Ok(workflows)
         }
 
-        /// Disable a workflow (only owner).
+        /// Updates the name and commandline of a workflow (requires `EditWorkflows`).
+        ///
+        /// The commandline is validated against the runner as in `add_workflow_with_runner`. If an operator
+        /// changes the commandline, the workflow loses its secret grants until the owner grants
+        /// them again.
         ///
         /// @category Workflow
         ///
         #[ink(message)]
-        pub fn disable_workflow(&mut self, id: WorkflowId) -> Result<()> {
-            self.ensure_owner()?;
+        pub fn update_workflow(
+            &mut self,
+            id: WorkflowId,
+            name: String,
+            commandline: String,
+        ) -> Result<()> {
+            self.ensure_permission(Permission::EditWorkflows)?;
             let mut workflow = self.ensure_workflow(id)?;
-            if workflow.enabled {
-                workflow.enabled = false;
-                self.workflows.insert(id, &workflow);
+            validate_commandline(&commandline, self.workflow_runners.get(id).as_ref())?;
+            if workflow.commandline != commandline {
+                self.revoke_secrets_unless_owner(id);
             }
+            workflow.name = name;
+            workflow.commandline = commandline;
+            self.workflows.insert(id, &workflow);
+            self.env().emit_event(WorkflowUpdated { workflow_id: id });
             Ok(())
         }
 
-        /// Get the EVM account address of given id.
+        /// Removes a workflow and its account bindings (requires `EditWorkflows`).
         ///
-        /// @category EvmAccount
+        /// A tombstone is left so the workflow id is never reused.
+        ///
+        /// @category Workflow
         ///
         #[ink(message)]
-        pub fn get_evm_account_address(&self, id: ExternalAccountId) -> Result<H160> {
-            let account = self.ensure_enabled_external_account(id)?;
-            let sk = pink_web3::keys::pink::KeyPair::from(account.sk);
-            Ok(sk.address())
-        }
+        pub fn remove_workflow(&mut self, id: WorkflowId) -> Result<()> {
+            self.ensure_permission(Permission::EditWorkflows)?;
+            self.ensure_workflow(id)?;
 
-        /// Get all EVM account addresses.
+            let mut accounts: Vec<ExternalAccountId> = Vec::new();
+            if let Some(account) = self.authorized_account.take(id) {
+                accounts.push(account);
+            }
+            for role in self.workflow_roles.take(id).unwrap_or_default() {
+                if let Some(account) = self.role_accounts.take((id, &role)) {
+                    accounts.push(account);
+                }
+            }
+            for account in accounts {
+                self.signing_policies.remove((id, account));
+            }
+            self.workflow_status.remove(id);
+            self.clear_workflow_schedule(id);
+            self.workflow_runners.remove(id);
+            for name in self.secret_names.get().unwrap_or_default() {
+                if let Some(mut secret) = self.secrets.get(&name) {
+                    secret.workflows.retain(|workflow| *workflow != id);
+                    self.secrets.insert(&name, &secret);
+                }
+            }
+            let now = self.env().block_timestamp();
+            self.workflows.remove(id);
+            self.removed_workflows.insert(id, &now);
+            self.env().emit_event(WorkflowRemoved { workflow_id: id });
+            Ok(())
+        }
+
+        /// Sets or clears the schedule of a workflow (requires `EditWorkflows`).
+        ///
+        /// @category Workflow
+        ///
+        #[ink(message)]
+        pub fn set_workflow_schedule(
+            &mut self,
+            id: WorkflowId,
+            schedule: Option<Schedule>,
+        ) -> Result<()> {
+            self.ensure_permission(Permission::EditWorkflows)?;
+            self.ensure_workflow(id)?;
+            match &schedule {
+                Some(schedule) => {
+                    match parse_schedule(schedule)? {
+                        Some(cron) => {
+                            self.workflow_crons.insert(id, &cron);
+                        }
+                        None => self.workflow_crons.remove(id),
+                    }
+                    if !self.workflow_schedules.contains(id) {
+                        let mut scheduled = self.scheduled_workflows.get().unwrap_or_default();
+                        scheduled.push(id);
+                        self.scheduled_workflows.set(&scheduled);
+                    }
+                    self.workflow_schedules.insert(id, schedule);
+                }
+                None => self.clear_workflow_schedule(id),
+            }
+            self.env().emit_event(WorkflowScheduleChanged {
+                workflow_id: id,
+                schedule,
+            });
+            Ok(())
+        }
+
+        /// Sets or clears the runner override of a workflow (requires `EditWorkflows`).
+        ///
+        /// A workflow without an override is run by the js_runner configured with `config()`. The
+        /// stored commandline must pass the validation of `add_workflow_with_runner` for the new
+        /// runner, so the override can only be cleared if the js_runner can run it. If an operator changes the runner, the workflow loses its secret grants
+        /// as with `update_workflow`.
+        ///
+        /// @category Workflow
+        ///
+        #[ink(message)]
+        pub fn set_workflow_runner(
+            &mut self,
+            id: WorkflowId,
+            runner: Option<WorkflowRunner>,
+        ) -> Result<()> {
+            self.ensure_permission(Permission::EditWorkflows)?;
+            let workflow = self.ensure_workflow(id)?;
+            if self.workflow_runners.get(id) != runner {
+                self.revoke_secrets_unless_owner(id);
+            }
+            validate_commandline(&workflow.commandline, runner.as_ref())?;
+            match &runner {
+                Some(runner) => {
+                    self.workflow_runners.insert(id, runner);
+                }
+                None => self.workflow_runners.remove(id),
+            }
+            self.env().emit_event(WorkflowRunnerChanged {
+                workflow_id: id,
+                runner,
+            });
+            Ok(())
+        }
+
+        /// Gets the runner a workflow is dispatched to, taking the profile js_runner by default.
+        ///
+        /// @category Workflow
+        ///
+        #[ink(message)]
+        pub fn get_workflow_runner(&self, id: WorkflowId) -> Result<WorkflowRunner> {
+            self.ensure_workflow(id)?;
+            self.workflow_runner(id)
+        }
+
+        /// Writes a secret to the vault (only owner).
+        ///
+        /// The workflows granted to an overwritten secret are kept.
+        ///
+        /// @category Secret
+        ///
+        #[ink(message)]
+        pub fn set_secret(&mut self, name: String, value: String) -> Result<()> {
+            self.ensure_owner()?;
+            ensure_valid_secret_name(&name)?;
+            let workflows = match self.secrets.get(&name) {
+                Some(secret) => secret.workflows,
+                None => {
+                    let mut names = self.secret_names.get().unwrap_or_default();
+                    names.push(name.clone());
+                    self.secret_names.set(&names);
+                    Vec::new()
+                }
+            };
+            let nonce: [u8; vault::NONCE_LEN] = pink::ext()
+                .getrandom(vault::NONCE_LEN as u8)
+                .try_into()
+                .expect("random is long enough; qed.");
+            let ciphertext = vault::apply_keystream(&self.vault_key(), &nonce, value.as_bytes());
+            let secret = Secret {
+                nonce,
+                ciphertext,
+                workflows,
+            };
+            self.secrets.insert(&name, &secret);
+            self.env().emit_event(SecretSet { name });
+            Ok(())
+        }
+
+        /// Removes a secret from the vault with its grants (only owner).
+        ///
+        /// @category Secret
+        ///
+        #[ink(message)]
+        pub fn remove_secret(&mut self, name: String) -> Result<()> {
+            self.ensure_owner()?;
+            self.ensure_secret(&name)?;
+            self.secrets.remove(&name);
+            let mut names = self.secret_names.get().unwrap_or_default();
+            names.retain(|n| *n != name);
+            self.secret_names.set(&names);
+            self.env().emit_event(SecretRemoved { name });
+            Ok(())
+        }
+
+        /// Allows a workflow to read a secret with `get_workflow_secret` (only owner).
+        ///
+        /// @category Secret
+        ///
+        #[ink(message)]
+        pub fn grant_secret(&mut self, name: String, workflow_id: WorkflowId) -> Result<()> {
+            self.ensure_owner()?;
+            self.ensure_workflow(workflow_id)?;
+            let mut secret = self.ensure_secret(&name)?;
+            if !secret.workflows.contains(&workflow_id) {
+                secret.workflows.push(workflow_id);
+                self.secrets.insert(&name, &secret);
+                self.env().emit_event(SecretGrantChanged {
+                    name,
+                    workflow_id,
+                    granted: true,
+                });
+            }
+            Ok(())
+        }
+
+        /// Disallows a workflow to read a secret (only owner).
+        ///
+        /// @category Secret
+        ///
+        #[ink(message)]
+        pub fn revoke_secret(&mut self, name: String, workflow_id: WorkflowId) -> Result<()> {
+            self.ensure_owner()?;
+            let mut secret = self.ensure_secret(&name)?;
+            if secret.workflows.contains(&workflow_id) {
+                secret.workflows.retain(|workflow| *workflow != workflow_id);
+                self.secrets.insert(&name, &secret);
+                self.env().emit_event(SecretGrantChanged {
+                    name,
+                    workflow_id,
+                    granted: false,
+                });
+            }
+            Ok(())
+        }
+
+        /// Lists the names of the secrets and the workflows granted to them, not the values.
+        ///
+        /// @category Secret
+        ///
+        #[ink(message)]
+        pub fn get_secrets(&self) -> Vec<SecretInfo> {
+            self.secret_names
+                .get()
+                .unwrap_or_default()
+                .into_iter()
+                .filter_map(|name| {
+                    let secret = self.secrets.get(&name)?;
+                    Some(SecretInfo {
+                        name,
+                        workflows: secret.workflows,
+                    })
+                })
+                .collect()
+        }
+
+        /// Lists the enabled workflows that should be polled at the given time.
+        ///
+        /// An interval workflow is due once the interval has passed since its last reported poll.
+        /// A cron workflow is due if `now` is in a matching minute and no poll has been reported
+        /// in that minute. Workflows without a schedule are never listed.
+        ///
+        /// @category Workflow
+        ///
+        #[ink(message)]
+        pub fn due_workflows(&self, now: Timestamp) -> Vec<WorkflowId> {
+            self.scheduled_workflows
+                .get()
+                .unwrap_or_default()
+                .into_iter()
+                .filter(|id| self.is_workflow_due(*id, now))
+                .collect()
+        }
+
+        /// Gets the removal time of a removed workflow.
+        ///
+        /// @category Workflow
+        ///
+        #[ink(message)]
+        pub fn get_workflow_removed_at(&self, id: WorkflowId) -> Option<Timestamp> {
+            self.removed_workflows.get(id)
+        }
+
+        /// Enable a workflow (requires `PauseWorkflows`).
+        ///
+        /// @category Workflow
+        ///
+        #[ink(message)]
+        pub fn enable_workflow(&mut self, id: WorkflowId) -> Result<()> {
+            self.ensure_permission(Permission::PauseWorkflows)?;
+            let mut workflow = self.ensure_workflow(id)?;
+            if !workflow.enabled {
+                workflow.enabled = true;
+                self.workflows.insert(id, &workflow);
+                self.env().emit_event(WorkflowEnabled { workflow_id: id });
+            }
+            Ok(())
+        }
+
+        /// Disable a workflow (requires `PauseWorkflows`).
+        ///
+        /// @category Workflow
+        ///
+        #[ink(message)]
+        pub fn disable_workflow(&mut self, id: WorkflowId) -> Result<()> {
+            self.ensure_permission(Permission::PauseWorkflows)?;
+            let mut workflow = self.ensure_workflow(id)?;
+            if workflow.enabled {
+                workflow.enabled = false;
+                self.workflows.insert(id, &workflow);
+                self.env().emit_event(WorkflowDisabled { workflow_id: id });
+            }
+            Ok(())
+        }
+
+        /// Get the EVM account address of given id.
+        ///
+        /// @category EvmAccount
+        ///
+        #[ink(message)]
+        pub fn get_evm_account_address(&self, id: ExternalAccountId) -> Result<H160> {
+            let account = self.ensure_evm_account(id)?;
+            let sk = pink_web3::keys::pink::KeyPair::from(account.sk);
+            Ok(sk.address())
+        }
+
+        /// Get all EVM account addresses.
         ///
         /// @category EvmAccount
         ///
@@ -328,12 +1420,13 @@ mod brick_profile {
             let mut accounts = Vec::new();
             for id in 0..self.next_external_account_id {
                 if let Some(account) = self.external_accounts.get(id) {
-                    if account.enabled {
+                    if account.enabled && account.key_type == KeyType::Evm {
                         let sk = pink_web3::keys::pink::KeyPair::from(account.sk);
                         accounts.push(ExternalAccountInfo {
                             id: account.id,
                             address: sk.address(),
-                            rpc: account.rpc.clone(),
+                            rpcs: account.rpcs.clone(),
+                            chain_id: account.chain_id,
                         });
                     }
                 }
@@ -341,45 +1434,244 @@ mod brick_profile {
             Ok(accounts)
         }
 
-        /// Get the EVM rpc endpoint of given id (only owner).
+        /// Get the native balances of all enabled EVM accounts (requires `ViewAccounts`).
+        ///
+        /// The balances are read from the rpc of each account.
+        ///
+        /// @category EvmAccount
+        ///
+        #[ink(message)]
+        pub fn get_account_balances(&self) -> Result<Vec<AccountBalance>> {
+            self.ensure_permission(Permission::ViewAccounts)?;
+            Ok(self.account_balances())
+        }
+
+        /// Get the enabled EVM accounts whose balances are below their low watermarks (requires
+        /// `ViewAccounts`).
+        ///
+        /// @category EvmAccount
+        ///
+        #[ink(message)]
+        pub fn get_low_funds_accounts(&self) -> Result<Vec<AccountBalance>> {
+            self.ensure_permission(Permission::ViewAccounts)?;
+            Ok(self
+                .account_balances()
+                .into_iter()
+                .filter(AccountBalance::is_low)
+                .collect())
+        }
+
+        /// Get the latest transactions signed with an EVM account, the newest first (requires
+        /// `ViewAccounts`).
+        ///
+        /// The log is kept in the local cache of the worker instead of the contract storage, and
+        /// only the last 64 transactions are kept. It only lists the transactions signed on the
+        /// worker serving this query since its cache was last evicted, so it can not tell which
+        /// workflows spent the funds of the account on the other workers.
+        ///
+        /// @category EvmAccount
+        ///
+        #[ink(message)]
+        pub fn get_signed_transactions(
+            &self,
+            id: ExternalAccountId,
+            offset: u64,
+            limit: u64,
+        ) -> Result<Vec<SignedTransaction>> {
+            self.ensure_permission(Permission::ViewAccounts)?;
+            self.ensure_evm_account(id)?;
+            Ok(load_signed_transactions(id)
+                .into_iter()
+                .rev()
+                .skip(offset as usize)
+                .take(limit as usize)
+                .collect())
+        }
+
+        /// Get the low watermark of the native balance of an EVM account (requires
+        /// `ViewAccounts`).
+        ///
+        /// @category EvmAccount
+        ///
+        #[ink(message)]
+        pub fn get_low_watermark(&self, id: ExternalAccountId) -> Result<Option<u128>> {
+            self.ensure_permission(Permission::ViewAccounts)?;
+            Ok(self.low_watermarks.get(id))
+        }
+
+        /// Set the low watermark of the native balance of an EVM account (requires
+        /// `ManageAccounts`).
+        ///
+        /// Accounts with balances below it are reported by `get_low_funds_accounts`. Pass `None`
+        /// to stop monitoring the account.
+        ///
+        /// @category EvmAccount
+        ///
+        #[ink(message)]
+        pub fn set_low_watermark(
+            &mut self,
+            id: ExternalAccountId,
+            low_watermark: Option<u128>,
+        ) -> Result<()> {
+            self.ensure_permission(Permission::ManageAccounts)?;
+            self.ensure_evm_account(id)?;
+            match low_watermark {
+                Some(low_watermark) => {
+                    self.low_watermarks.insert(id, &low_watermark);
+                }
+                None => self.low_watermarks.remove(id),
+            }
+            self.env().emit_event(LowWatermarkChanged {
+                account_id: id,
+                low_watermark,
+            });
+            Ok(())
+        }
+
+        /// Get the EVM rpc endpoint of given id in use (requires `ViewAccounts`).
+        ///
+        /// It is the first endpoint that has not failed recently.
         ///
         /// @category EvmAccount
         ///
         #[ink(message)]
         pub fn get_rpc_endpoint(&self, id: ExternalAccountId) -> Result<String> {
-            self.ensure_owner()?;
+            self.ensure_permission(Permission::ViewAccounts)?;
+            let account = self.ensure_enabled_external_account(id)?;
+            self.active_rpc(&account)
+        }
+
+        /// Get all the rpc endpoints of given id in the order of preference (requires
+        /// `ViewAccounts`).
+        ///
+        /// @category EvmAccount
+        ///
+        #[ink(message)]
+        pub fn get_rpc_endpoints(&self, id: ExternalAccountId) -> Result<Vec<String>> {
+            self.ensure_permission(Permission::ViewAccounts)?;
             let account = self.ensure_enabled_external_account(id)?;
-            Ok(account.rpc.clone())
+            Ok(account.rpcs)
         }
 
-        /// Set the EVM rpc endpoint of given id (only owner).
+        /// Set the EVM rpc endpoint of given id (requires `ManageAccounts`).
+        ///
+        /// This replaces the whole failover list with the single endpoint given.
+        ///
+        /// Deprecated, use `set_rpc_endpoints` instead.
         ///
         /// @category EvmAccount
         ///
         #[ink(message)]
         pub fn set_rpc_endpoint(&mut self, id: ExternalAccountId, rpc: String) -> Result<()> {
-            self.ensure_owner()?;
+            self.set_rpc_endpoints(id, vec![rpc])
+        }
+
+        /// Set the rpc endpoints of given id in the order of preference (requires
+        /// `ManageAccounts`).
+        ///
+        /// The endpoints must serve the same chain. The later ones are only used when the former
+        /// ones fail. This also clears the cached chain id since the new rpcs may point to
+        /// another chain.
+        ///
+        /// @category EvmAccount
+        ///
+        #[ink(message)]
+        pub fn set_rpc_endpoints(
+            &mut self,
+            id: ExternalAccountId,
+            rpcs: Vec<String>,
+        ) -> Result<()> {
+            self.ensure_permission(Permission::ManageAccounts)?;
+            if rpcs.is_empty() {
+                return Err(Error::NoRpcEndpoint);
+            }
+            let mut account = self.ensure_enabled_external_account(id)?;
+            account.rpcs = rpcs.clone();
+            account.chain_id = None;
+            self.external_accounts.insert(id, &account);
+            self.env().emit_event(RpcEndpointsChanged {
+                account_id: id,
+                rpcs,
+            });
+            Ok(())
+        }
+
+        /// Set whether reads of the chain id and block number need two rpc endpoints to agree
+        /// (requires `ManageAccounts`).
+        ///
+        /// @category EvmAccount
+        ///
+        #[ink(message)]
+        pub fn set_rpc_quorum(&mut self, id: ExternalAccountId, enabled: bool) -> Result<()> {
+            self.ensure_permission(Permission::ManageAccounts)?;
             let mut account = self.ensure_enabled_external_account(id)?;
-            account.rpc = rpc;
+            account.rpc_quorum = enabled;
             self.external_accounts.insert(id, &account);
+            self.env().emit_event(RpcQuorumChanged {
+                account_id: id,
+                enabled,
+            });
             Ok(())
         }
 
-        /// Get EVM chain id of given id (only owner).
+        /// Get the latest block number of the chain of given id (requires `ViewAccounts`).
+        ///
+        /// @category EvmAccount
+        ///
+        #[ink(message)]
+        pub fn get_block_number(&self, id: ExternalAccountId) -> Result<U64> {
+            self.ensure_permission(Permission::ViewAccounts)?;
+            let account = self.ensure_evm_account(id)?;
+            self.call_rpc_with_quorum(&account, |web3| resolve_ready(web3.eth().block_number()))
+                .map_err(Error::FailedToReadBlockNumber)
+        }
+
+        /// Get EVM chain id of given id (requires `ViewAccounts`).
+        ///
+        /// The cached chain id is returned if there is one, otherwise it is read from the rpc.
         ///
         /// @category EvmAccount
         ///
         #[ink(message)]
         pub fn get_chain_id(&self, id: ExternalAccountId) -> Result<U256> {
-            self.ensure_owner()?;
-            let account = self.ensure_enabled_external_account(id)?;
+            self.ensure_permission(Permission::ViewAccounts)?;
+            let account = self.ensure_evm_account(id)?;
+            let chain_id = self.ensure_chain_id(&account)?;
+            Ok(chain_id.into())
+        }
 
-            let phttp = PinkHttp::new(account.rpc.clone());
-            let web3 = pink_web3::Web3::new(phttp);
-            let chain_id = resolve_ready(web3.eth().chain_id())
-                .map_err(|err| Error::FailedToReadChainId(format!("{:?}", err)))?;
+        /// Set the cached EVM chain id of given id (requires `ManageAccounts`).
+        ///
+        /// The signer uses the cached chain id for EIP-155 replay protection instead of asking the
+        /// rpc every time. Pass `None` to clear the cache.
+        ///
+        /// @category EvmAccount
+        ///
+        #[ink(message)]
+        pub fn set_chain_id(&mut self, id: ExternalAccountId, chain_id: Option<u64>) -> Result<()> {
+            self.ensure_permission(Permission::ManageAccounts)?;
+            let mut account = self.ensure_evm_account(id)?;
+            account.chain_id = chain_id;
+            self.external_accounts.insert(id, &account);
+            self.env().emit_event(ChainIdChanged {
+                account_id: id,
+                chain_id,
+            });
+            Ok(())
+        }
 
-            Ok(chain_id)
+        /// Get the next nonce to use for the EVM account (requires `ViewAccounts`).
+        ///
+        /// It is the larger one of the pending transaction count reported by the rpc and the
+        /// nonces reserved or signed by the workflows on this worker.
+        ///
+        /// @category EvmAccount
+        ///
+        #[ink(message)]
+        pub fn get_pending_nonce(&self, id: ExternalAccountId) -> Result<U256> {
+            self.ensure_permission(Permission::ViewAccounts)?;
+            let account = self.ensure_evm_account(id)?;
+            self.pending_nonce(&account)
         }
 
         /// Gets the total number of external accounts.
@@ -393,13 +1685,13 @@ mod brick_profile {
             self.next_external_account_id
         }
 
-        /// Generates a new EVM account (only owner).
+        /// Generates a new EVM account (requires `ManageAccounts`).
         ///
         /// @category EvmAccount
         ///
         #[ink(message)]
         pub fn generate_evm_account(&mut self, rpc: String) -> Result<ExternalAccountId> {
-            self.ensure_owner()?;
+            self.ensure_permission(Permission::ManageAccounts)?;
 
             let id = self.next_external_account_id;
             let random = signing::derive_sr25519_key(&id.to_be_bytes());
@@ -407,11 +1699,19 @@ mod brick_profile {
                 id,
                 enabled: true,
                 account_type: ExternalAccountType::Generated,
-                rpc,
+                rpcs: vec![rpc],
                 sk: random[..32].try_into().or(Err(Error::BadEvmSecretKey))?,
+                chain_id: None,
+                key_type: KeyType::Evm,
+                rpc_quorum: false,
             };
             self.external_accounts.insert(id, &evm_account);
             self.next_external_account_id += 1;
+            self.env().emit_event(ExternalAccountCreated {
+                account_id: id,
+                account_type: evm_account.account_type,
+                key_type: evm_account.key_type,
+            });
 
             Ok(id)
         }
@@ -439,11 +1739,19 @@ mod brick_profile {
                 id,
                 enabled: true,
                 account_type: ExternalAccountType::Imported,
-                rpc,
+                rpcs: vec![rpc],
                 sk: sk.try_into().or(Err(Error::BadEvmSecretKey))?,
+                chain_id: None,
+                key_type: KeyType::Evm,
+                rpc_quorum: false,
             };
             self.external_accounts.insert(id, &evm_account);
             self.next_external_account_id += 1;
+            self.env().emit_event(ExternalAccountCreated {
+                account_id: id,
+                account_type: evm_account.account_type,
+                key_type: evm_account.key_type,
+            });
 
             Ok(id)
         }
@@ -456,16 +1764,23 @@ mod brick_profile {
         pub fn dump_evm_account(&mut self, id: ExternalAccountId) -> Result<()> {
             self.ensure_owner()?;
 
-            let mut account = self.ensure_enabled_external_account(id)?;
+            let mut account = self.ensure_evm_account(id)?;
             account.enabled = false;
             account.account_type = ExternalAccountType::Dumped;
             self.external_accounts.insert(id, &account);
+            self.env().emit_event(ExternalAccountDisabled {
+                account_id: id,
+                account_type: account.account_type,
+            });
 
             Ok(())
         }
 
         /// Get the secret key of a dumped EVM account (only owner).
         ///
+        /// The key is returned in plaintext, use `export_evm_account` to move an account to
+        /// another profile instead.
+        ///
         /// @category EvmAccount
         ///
         #[ink(message)]
@@ -475,33 +1790,403 @@ mod brick_profile {
             Ok(account.sk)
         }
 
-        /// Authorize workflow to use account (only owner).
+        /// Get the public key that exported keys for this profile must be encrypted to.
         ///
-        /// @category Workflow
+        /// It is a compressed secp256k1 public key (33 bytes).
+        ///
+        /// @category EvmAccount
         ///
         #[ink(message)]
-        pub fn authorize_workflow(
+        pub fn get_import_public_key(&self) -> Result<Vec<u8>> {
+            Ok(signing::get_public_key(&self.import_key()?, SigType::Ecdsa))
+        }
+
+        /// Export an EVM account to another profile, this will disable the account (only owner).
+        ///
+        /// The encrypted key can then be read with `get_exported_key`.
+        ///
+        /// @category EvmAccount
+        ///
+        #[ink(message)]
+        pub fn export_evm_account(
             &mut self,
-            workflow: WorkflowId,
-            account: ExternalAccountId,
+            id: ExternalAccountId,
+            to_profile: AccountId,
         ) -> Result<()> {
             self.ensure_owner()?;
 
-            self.ensure_workflow(workflow)?;
-            self.ensure_external_account(account)?;
-            self.authorized_account.insert(workflow, &account);
+            let mut account = self.ensure_evm_account(id)?;
+            account.enabled = false;
+            account.account_type = ExternalAccountType::Exported { to_profile };
+            self.external_accounts.insert(id, &account);
+            self.env().emit_event(ExternalAccountDisabled {
+                account_id: id,
+                account_type: account.account_type,
+            });
+
             Ok(())
         }
 
-        /// Get the authorized external account id of given workflow.
+        /// Get the key of an exported EVM account encrypted to the target profile (only owner).
         ///
-        /// @category Workflow
+        /// # Arguments
+        /// * `id` - The exported account id.
+        /// * `recipient_public_key` - The `get_import_public_key()` of the target profile.
         ///
-        #[ink(message)]
+        /// @category EvmAccount
+        ///
+        #[ink(message)]
+        pub fn get_exported_key(
+            &self,
+            id: ExternalAccountId,
+            recipient_public_key: Vec<u8>,
+        ) -> Result<ExportedKey> {
+            self.ensure_owner()?;
+            let account = self.ensure_external_account(id)?;
+            let ExternalAccountType::Exported { to_profile } = account.account_type else {
+                return Err(Error::OnlyExportedAccount);
+            };
+
+            let ephemeral_secret = pink::ext().getrandom(32);
+            let ephemeral_public = signing::get_public_key(&ephemeral_secret, SigType::Ecdsa);
+            let ciphertext = ecies::encrypt(
+                &recipient_public_key,
+                &ephemeral_secret,
+                &ephemeral_public,
+                &account.sk,
+            )
+            .or(Err(Error::BadPublicKey))?;
+            let mut exported = ExportedKey {
+                from_profile: self.env().account_id(),
+                from_account: id,
+                to_profile,
+                ciphertext,
+                proof: Vec::new(),
+            };
+            exported.proof = signing::sign(&exported.proof_message(), &account.sk, SigType::Ecdsa);
+            Ok(exported)
+        }
+
+        /// Imports an EVM account exported by another profile (only owner).
+        ///
+        /// The key must be encrypted to this profile and signed by the exported key itself. Each
+        /// exported account can only be imported once.
+        ///
+        /// @category EvmAccount
+        ///
+        #[ink(message)]
+        pub fn import_exported_evm_account(
+            &mut self,
+            rpc: String,
+            exported: ExportedKey,
+        ) -> Result<ExternalAccountId> {
+            self.ensure_owner()?;
+
+            if exported.to_profile != self.env().account_id() {
+                return Err(Error::BadExportedKey);
+            }
+            let from = (exported.from_profile, exported.from_account);
+            if self.imported_accounts.contains(from) {
+                return Err(Error::AccountAlreadyImported);
+            }
+            let sk = ecies::decrypt(&self.import_key()?, &exported.ciphertext)
+                .or(Err(Error::BadExportedKey))?;
+            let pubkey = signing::get_public_key(&sk, SigType::Ecdsa);
+            if !signing::verify(
+                &exported.proof_message(),
+                &pubkey,
+                &exported.proof,
+                SigType::Ecdsa,
+            ) {
+                return Err(Error::BadExportedKey);
+            }
+
+            let id = self.next_external_account_id;
+            let evm_account = ExternalAccount {
+                id,
+                enabled: true,
+                account_type: ExternalAccountType::Migrated {
+                    from_profile: exported.from_profile,
+                    from_account: exported.from_account,
+                },
+                rpcs: vec![rpc],
+                sk,
+                chain_id: None,
+                key_type: KeyType::Evm,
+                rpc_quorum: false,
+            };
+            self.external_accounts.insert(id, &evm_account);
+            self.imported_accounts.insert(from, &id);
+            self.next_external_account_id += 1;
+            self.env().emit_event(ExternalAccountCreated {
+                account_id: id,
+                account_type: evm_account.account_type,
+                key_type: evm_account.key_type,
+            });
+
+            Ok(id)
+        }
+
+        /// Generates a new Substrate account (requires `ManageAccounts`).
+        ///
+        /// The account shares the id space with EVM accounts and is authorized to workflows in the
+        /// same way.
+        ///
+        /// # Arguments
+        /// * `rpc` - The rpc endpoint of the Substrate chain.
+        /// * `scheme` - The signature scheme of the account.
+        /// * `ss58_prefix` - The SS58 network prefix of the chain used to format the address.
+        ///
+        /// @category SubstrateAccount
+        ///
+        #[ink(message)]
+        pub fn generate_substrate_account(
+            &mut self,
+            rpc: String,
+            scheme: SubstrateScheme,
+            ss58_prefix: u16,
+        ) -> Result<ExternalAccountId> {
+            self.ensure_permission(Permission::ManageAccounts)?;
+            if ss58_prefix > ss58::MAX_PREFIX {
+                return Err(Error::InvalidSs58Prefix);
+            }
+
+            let id = self.next_external_account_id;
+            let random = signing::derive_sr25519_key(&id.to_be_bytes());
+            let account = ExternalAccount {
+                id,
+                enabled: true,
+                account_type: ExternalAccountType::Generated,
+                rpcs: vec![rpc],
+                sk: random[..32]
+                    .try_into()
+                    .or(Err(Error::BadSubstrateSecretKey))?,
+                chain_id: None,
+                key_type: KeyType::Substrate {
+                    scheme,
+                    ss58_prefix,
+                },
+                rpc_quorum: false,
+            };
+            self.external_accounts.insert(id, &account);
+            self.next_external_account_id += 1;
+            self.env().emit_event(ExternalAccountCreated {
+                account_id: id,
+                account_type: account.account_type,
+                key_type: account.key_type,
+            });
+
+            Ok(id)
+        }
+
+        /// Get the SS58 address of a Substrate account.
+        ///
+        /// @category SubstrateAccount
+        ///
+        #[ink(message)]
+        pub fn get_substrate_account_address(&self, id: ExternalAccountId) -> Result<String> {
+            let (account, scheme, ss58_prefix) = self.ensure_substrate_account(id)?;
+            Ok(substrate_account_info(&account, scheme, ss58_prefix).address)
+        }
+
+        /// Get all Substrate accounts.
+        ///
+        /// @category SubstrateAccount
+        ///
+        #[ink(message)]
+        pub fn get_all_substrate_accounts(&self) -> Result<Vec<SubstrateAccountInfo>> {
+            let mut accounts = Vec::new();
+            for id in 0..self.next_external_account_id {
+                let Some(account) = self.external_accounts.get(id) else {
+                    continue;
+                };
+                if let KeyType::Substrate {
+                    scheme,
+                    ss58_prefix,
+                } = account.key_type
+                {
+                    if account.enabled {
+                        accounts.push(substrate_account_info(&account, scheme, ss58_prefix));
+                    }
+                }
+            }
+            Ok(accounts)
+        }
+
+        /// Authorize workflow to use account (requires `AuthorizeWorkflows`).
+        ///
+        /// @category Workflow
+        ///
+        #[ink(message)]
+        pub fn authorize_workflow(
+            &mut self,
+            workflow: WorkflowId,
+            account: ExternalAccountId,
+        ) -> Result<()> {
+            self.ensure_permission(Permission::AuthorizeWorkflows)?;
+
+            self.ensure_workflow(workflow)?;
+            self.ensure_external_account(account)?;
+            self.authorized_account.insert(workflow, &account);
+            self.env().emit_event(WorkflowAuthorizationChanged {
+                workflow_id: workflow,
+                account_id: Some(account),
+                role: DEFAULT_ROLE.into(),
+            });
+            Ok(())
+        }
+
+        /// Get the authorized external account id of given workflow.
+        ///
+        /// @category Workflow
+        ///
+        #[ink(message)]
         pub fn get_authorized_account(&self, workflow: WorkflowId) -> Option<ExternalAccountId> {
             self.authorized_account.get(workflow)
         }
 
+        /// Authorize workflow to use account under a named role (requires `AuthorizeWorkflows`).
+        ///
+        /// A workflow can hold several accounts under different roles, e.g. `gas_payer` and
+        /// `treasury`. The `default` role is the same as `authorize_workflow`.
+        ///
+        /// @category Workflow
+        ///
+        #[ink(message)]
+        pub fn authorize_workflow_role(
+            &mut self,
+            workflow: WorkflowId,
+            role: String,
+            account: ExternalAccountId,
+        ) -> Result<()> {
+            self.ensure_permission(Permission::AuthorizeWorkflows)?;
+
+            ensure_valid_role(&role)?;
+            self.ensure_workflow(workflow)?;
+            self.ensure_external_account(account)?;
+            if role == DEFAULT_ROLE {
+                self.authorized_account.insert(workflow, &account);
+            } else {
+                self.role_accounts.insert((workflow, &role), &account);
+                let mut roles = self.workflow_roles.get(workflow).unwrap_or_default();
+                if !roles.contains(&role) {
+                    roles.push(role.clone());
+                    self.workflow_roles.insert(workflow, &roles);
+                }
+            }
+            self.env().emit_event(WorkflowAuthorizationChanged {
+                workflow_id: workflow,
+                account_id: Some(account),
+                role,
+            });
+            Ok(())
+        }
+
+        /// Revoke the account binding of a workflow role (requires `AuthorizeWorkflows`).
+        ///
+        /// Fails with `RoleNotBound` if the role has no account bound.
+        ///
+        /// @category Workflow
+        ///
+        #[ink(message)]
+        pub fn revoke_workflow_role(&mut self, workflow: WorkflowId, role: String) -> Result<()> {
+            self.ensure_permission(Permission::AuthorizeWorkflows)?;
+
+            ensure_valid_role(&role)?;
+            self.ensure_workflow(workflow)?;
+            if self.authorized_account_by_role(workflow, &role).is_none() {
+                return Err(Error::RoleNotBound);
+            }
+            if role == DEFAULT_ROLE {
+                self.authorized_account.remove(workflow);
+            } else {
+                self.role_accounts.remove((workflow, &role));
+                let mut roles = self.workflow_roles.get(workflow).unwrap_or_default();
+                roles.retain(|r| *r != role);
+                self.workflow_roles.insert(workflow, &roles);
+            }
+            self.env().emit_event(WorkflowAuthorizationChanged {
+                workflow_id: workflow,
+                account_id: None,
+                role,
+            });
+            Ok(())
+        }
+
+        /// Get the authorized external account id of given workflow under the role.
+        ///
+        /// @category Workflow
+        ///
+        #[ink(message)]
+        pub fn get_authorized_account_by_role(
+            &self,
+            workflow: WorkflowId,
+            role: String,
+        ) -> Option<ExternalAccountId> {
+            self.authorized_account_by_role(workflow, &role)
+        }
+
+        /// Set the signing policy of a workflow on an external account (requires
+        /// `AuthorizeWorkflows`).
+        ///
+        /// The policy is checked every time the workflow asks the account to sign a transaction.
+        ///
+        /// @category Workflow
+        ///
+        #[ink(message)]
+        pub fn set_signing_policy(
+            &mut self,
+            workflow: WorkflowId,
+            account: ExternalAccountId,
+            policy: SigningPolicy,
+        ) -> Result<()> {
+            self.ensure_permission(Permission::AuthorizeWorkflows)?;
+
+            self.ensure_workflow(workflow)?;
+            self.ensure_external_account(account)?;
+            self.signing_policies.insert((workflow, account), &policy);
+            self.env().emit_event(SigningPolicyChanged {
+                workflow_id: workflow,
+                account_id: account,
+                policy: Some(policy),
+            });
+            Ok(())
+        }
+
+        /// Remove the signing policy of a workflow on an external account (requires
+        /// `AuthorizeWorkflows`).
+        ///
+        /// @category Workflow
+        ///
+        #[ink(message)]
+        pub fn remove_signing_policy(
+            &mut self,
+            workflow: WorkflowId,
+            account: ExternalAccountId,
+        ) -> Result<()> {
+            self.ensure_permission(Permission::AuthorizeWorkflows)?;
+            self.signing_policies.remove((workflow, account));
+            self.env().emit_event(SigningPolicyChanged {
+                workflow_id: workflow,
+                account_id: account,
+                policy: None,
+            });
+            Ok(())
+        }
+
+        /// Get the signing policy of a workflow on an external account.
+        ///
+        /// @category Workflow
+        ///
+        #[ink(message)]
+        pub fn get_signing_policy(
+            &self,
+            workflow: WorkflowId,
+            account: ExternalAccountId,
+        ) -> Option<SigningPolicy> {
+            self.signing_policies.get((workflow, account))
+        }
+
         /// Force poll a workflow without checking the workflow enabled status.
         ///
         /// # Arguments
@@ -512,43 +2197,61 @@ mod brick_profile {
         ///
         #[ink(message)]
         pub fn force_poll(&mut self, workflow_id: WorkflowId, poll_id: String) -> Result<bool> {
-            use ink::env::call::{build_call, ExecutionInput, Selector};
             // Trick here: We only allow Query the `poll()` function, so the following `workflow_session` change only
             // lives in this call and is never written back to chain.
             if pink::ext().is_in_transaction() {
                 return Err(Error::NoPollForTransaction);
             }
 
-            if poll_id.len() > 16 {
-                return Err(Error::InvalidPollId);
-            }
-            // Ensure the poll_id only contains a-zA-Z0-9_- to prevent XSS.
-            if !poll_id
-                .chars()
-                .all(|c| c.is_ascii_alphanumeric() || c == '_' || c == '-')
-            {
-                return Err(Error::InvalidPollId);
-            }
+            ensure_valid_poll_id(&poll_id)?;
             let _span = logging::enter_span(&format!("poll_id={poll_id}"));
             let profile = hex_fmt::HexFmt(self.env().account_id());
             info!("polling profile 0x{profile}:{workflow_id}");
 
             let now_workflow = self.ensure_workflow(workflow_id)?;
-            self.workflow_session.set(&now_workflow.id);
-            let js_runner = self.get_js_runner()?;
-            let call_result = build_call::<pink::PinkEnvironment>()
-                .call(js_runner)
-                // .gas_limit(5000)
-                .transferred_value(0)
-                .call_flags(ink::env::CallFlags::default().set_allow_reentry(true))
-                .exec_input(
-                    // pub fn run(&self, actions: String) -> bool, 0xb95b5eb3
-                    ExecutionInput::new(Selector::new(ink::selector_bytes!("run")))
-                        .push_arg(now_workflow.commandline),
-                )
-                .returns::<bool>()
-                .invoke();
-            Ok(call_result)
+            self.run_workflow(now_workflow)
+        }
+
+        /// Polls several workflows one after another in a single query.
+        ///
+        /// Each workflow runs in its own session, and its failure does not affect the others.
+        /// Returns the result of each workflow in the given order, which is the same as what
+        /// `poll()` returns for it.
+        ///
+        /// # Arguments
+        /// * `workflow_ids` - The workflow ids.
+        /// * `poll_id` - A unique id for this poll (16 chars max), shared by the workflows.
+        ///
+        /// @category Polling
+        ///
+        #[ink(message)]
+        pub fn poll_many(
+            &mut self,
+            workflow_ids: Vec<WorkflowId>,
+            poll_id: String,
+        ) -> Result<Vec<Result<bool>>> {
+            // Same as `force_poll()`, the sessions are never written back to chain
+            if pink::ext().is_in_transaction() {
+                return Err(Error::NoPollForTransaction);
+            }
+
+            ensure_valid_poll_id(&poll_id)?;
+            let _span = logging::enter_span(&format!("poll_id={poll_id}"));
+            let profile = hex_fmt::HexFmt(self.env().account_id());
+
+            let results = workflow_ids
+                .into_iter()
+                .map(|workflow_id| {
+                    info!("polling profile 0x{profile}:{workflow_id}");
+                    let now_workflow = self.ensure_enabled_workflow(workflow_id)?;
+                    let result = self.run_workflow(now_workflow);
+                    if let Err(err) = &result {
+                        info!("workflow {workflow_id} failed: {err:?}");
+                    }
+                    result
+                })
+                .collect();
+            Ok(results)
         }
 
         /// Called by a scheduler periodically with Query.
@@ -565,319 +2268,2787 @@ mod brick_profile {
             self.force_poll(workflow_id, poll_id)
         }
 
-        /// Only self-initiated call is allowed.
+        /// Reports the result of a poll (only owner or poll reporter).
+        ///
+        /// Polls run as queries and cannot record anything, so the scheduler is expected to
+        /// report the result of each poll with a transaction.
+        ///
+        /// # Arguments
+        /// * `workflow_id` - The workflow id.
+        /// * `poll_id` - The poll id passed to `poll()`.
+        /// * `succeeded` - Whether the poll returns `Ok(true)`.
+        /// * `error` - The error of a failed poll, truncated to 256 bytes.
         ///
         /// @category Polling
         ///
         #[ink(message)]
-        pub fn get_current_evm_account_address(&self) -> Result<H160> {
-            let now_workflow_id = self.ensure_workflow_session()?;
-            let account_id = self
-                .authorized_account
-                .get(now_workflow_id)
-                .ok_or(Error::NoAuthorizedExternalAccount)?;
-            info!("Workflow {now_workflow_id} reads account {account_id} address");
+        pub fn report_poll(
+            &mut self,
+            workflow_id: WorkflowId,
+            poll_id: String,
+            succeeded: bool,
+            error: Option<String>,
+        ) -> Result<()> {
+            self.ensure_poll_reporter()?;
+            ensure_valid_poll_id(&poll_id)?;
+            self.ensure_workflow(workflow_id)?;
 
-            let account = self.ensure_enabled_external_account(account_id)?;
-            let sk = pink_web3::keys::pink::KeyPair::from(account.sk);
-            Ok(sk.address())
+            let now = self.env().block_timestamp();
+            let mut status = self.workflow_status.get(workflow_id).unwrap_or_default();
+            status.last_poll_id = Some(poll_id.clone());
+            status.last_poll_at = Some(now);
+            if succeeded {
+                status.last_success_at = Some(now);
+                status.consecutive_failures = 0;
+                status.last_error = None;
+            } else {
+                status.consecutive_failures = status.consecutive_failures.saturating_add(1);
+                status.last_error = error.map(truncate_poll_error);
+            }
+            self.workflow_status.insert(workflow_id, &status);
+            self.env().emit_event(PollReported {
+                workflow_id,
+                poll_id,
+                succeeded,
+            });
+            Ok(())
         }
 
-        /// Only self-initiated call is allowed.
+        /// Gets the poll history of a workflow.
         ///
         /// @category Polling
         ///
         #[ink(message)]
-        pub fn get_current_rpc(&self) -> Result<String> {
-            let now_workflow_id = self.ensure_workflow_session()?;
-            let account_id = self
-                .authorized_account
-                .get(now_workflow_id)
-                .ok_or(Error::NoAuthorizedExternalAccount)?;
-            info!("Workflow {now_workflow_id} reads account {account_id} rpc");
+        pub fn get_workflow_status(&self, workflow_id: WorkflowId) -> Result<WorkflowStatus> {
+            self.ensure_workflow(workflow_id)?;
+            Ok(self.workflow_status.get(workflow_id).unwrap_or_default())
+        }
 
-            let account = self.ensure_enabled_external_account(account_id)?;
-            Ok(account.rpc)
+        /// Gets the account allowed to report poll results besides the owner.
+        ///
+        /// @category Polling
+        ///
+        #[ink(message)]
+        pub fn get_poll_reporter(&self) -> Option<AccountId> {
+            self.poll_reporter.get().flatten()
         }
 
-        /// Only self-initiated call is allowed.
+        /// Sets the account allowed to report poll results besides the owner (only owner).
         ///
         /// @category Polling
         ///
         #[ink(message)]
-        pub fn sign_evm_transaction(&self, tx: Vec<u8>) -> Result<Vec<u8>> {
-            let now_workflow_id = self.ensure_workflow_session()?;
-            info!("Workflow {} asks for EVM tx signing", now_workflow_id);
+        pub fn set_poll_reporter(&mut self, reporter: Option<AccountId>) -> Result<()> {
+            self.ensure_owner()?;
+            self.poll_reporter.set(&reporter);
+            self.env().emit_event(PollReporterChanged { reporter });
+            Ok(())
+        }
 
-            let account_id = self
-                .authorized_account
-                .get(now_workflow_id)
-                .ok_or(Error::NoAuthorizedExternalAccount)?;
-            let account = self.ensure_enabled_external_account(account_id)?;
-            info!("ExternalAccount {} is allowed", account_id);
+        /// Grant an operator role to an account (only owner).
+        ///
+        /// @category Operator
+        ///
+        #[ink(message)]
+        pub fn grant_operator_role(
+            &mut self,
+            operator: AccountId,
+            role: OperatorRole,
+        ) -> Result<()> {
+            self.ensure_owner()?;
 
-            let phttp = PinkHttp::new(account.rpc.clone());
-            let web3 = pink_web3::Web3::new(phttp);
-            let sk = pink_web3::keys::pink::KeyPair::from(account.sk);
+            let mut roles = self.operator_roles.get(operator).unwrap_or_default();
+            if roles.contains(&role) {
+                return Ok(());
+            }
+            roles.push(role);
+            self.operator_roles.insert(operator, &roles);
+            let mut operators = self.operators.get().unwrap_or_default();
+            if !operators.contains(&operator) {
+                operators.push(operator);
+                self.operators.set(&operators);
+            }
+            self.env()
+                .emit_event(OperatorRoleGranted { operator, role });
+            Ok(())
+        }
 
-            let tx: TransactionRequest =
-                json::from_slice(&tx).or(Err(Error::BadUnsignedTransaction))?;
-            let tx = TransactionParameters {
-                nonce: tx.nonce,
-                to: tx.to,
-                gas: tx.gas.unwrap_or_default(),
-                gas_price: tx.gas_price,
-                value: tx.value.unwrap_or_default(),
-                data: tx.data.unwrap_or_default(),
-                transaction_type: tx.transaction_type,
+        /// Revoke an operator role from an account (only owner).
+        ///
+        /// @category Operator
+        ///
+        #[ink(message)]
+        pub fn revoke_operator_role(
+            &mut self,
+            operator: AccountId,
+            role: OperatorRole,
+        ) -> Result<()> {
+            self.ensure_owner()?;
+
+            let mut roles = self.operator_roles.get(operator).unwrap_or_default();
+            if !roles.contains(&role) {
+                return Ok(());
+            }
+            roles.retain(|r| *r != role);
+            if roles.is_empty() {
+                self.operator_roles.remove(operator);
+                let mut operators = self.operators.get().unwrap_or_default();
+                operators.retain(|o| *o != operator);
+                self.operators.set(&operators);
+            } else {
+                self.operator_roles.insert(operator, &roles);
+            }
+            self.env()
+                .emit_event(OperatorRoleRevoked { operator, role });
+            Ok(())
+        }
+
+        /// Get the operator roles of an account.
+        ///
+        /// @category Operator
+        ///
+        #[ink(message)]
+        pub fn get_operator_roles(&self, operator: AccountId) -> Vec<OperatorRole> {
+            self.operator_roles.get(operator).unwrap_or_default()
+        }
+
+        /// Get all the operators with their roles.
+        ///
+        /// @category Operator
+        ///
+        #[ink(message)]
+        pub fn get_operators(&self) -> Vec<(AccountId, Vec<OperatorRole>)> {
+            self.operators
+                .get()
+                .unwrap_or_default()
+                .into_iter()
+                .map(|operator| (operator, self.get_operator_roles(operator)))
+                .collect()
+        }
+
+        /// Returns whether the account has the permission, either as the owner or by its roles.
+        ///
+        /// @category Operator
+        ///
+        #[ink(message)]
+        pub fn has_permission(&self, account: AccountId, permission: Permission) -> bool {
+            self.ownership.is_owner(&account)
+                || self
+                    .get_operator_roles(account)
+                    .iter()
+                    .any(|role| role.permissions().contains(&permission))
+        }
+
+        /// Only self-initiated call is allowed.
+        ///
+        /// @category Polling
+        ///
+        #[ink(message)]
+        pub fn get_current_evm_account_address(&self) -> Result<H160> {
+            self.get_current_evm_account_address_by_role(DEFAULT_ROLE.into())
+        }
+
+        /// Only self-initiated call is allowed.
+        ///
+        /// @category Polling
+        ///
+        #[ink(message)]
+        pub fn get_current_evm_account_address_by_role(&self, role: String) -> Result<H160> {
+            let (now_workflow_id, account_id) = self.ensure_session_account(&role)?;
+            info!("Workflow {now_workflow_id} reads account {account_id} address as {role}");
+
+            let account = self.ensure_evm_account(account_id)?;
+            let sk = pink_web3::keys::pink::KeyPair::from(account.sk);
+            Ok(sk.address())
+        }
+
+        /// Only self-initiated call is allowed.
+        ///
+        /// @category Polling
+        ///
+        #[ink(message)]
+        pub fn get_current_rpc(&self) -> Result<String> {
+            self.get_current_rpc_by_role(DEFAULT_ROLE.into())
+        }
+
+        /// Only self-initiated call is allowed.
+        ///
+        /// @category Polling
+        ///
+        #[ink(message)]
+        pub fn get_current_rpc_by_role(&self, role: String) -> Result<String> {
+            let (now_workflow_id, account_id) = self.ensure_session_account(&role)?;
+            info!("Workflow {now_workflow_id} reads account {account_id} rpc as {role}");
+
+            let account = self.ensure_enabled_external_account(account_id)?;
+            self.active_rpc(&account)
+        }
+
+        /// Only self-initiated call is allowed.
+        ///
+        /// Returns the secret granted to the workflow.
+        ///
+        /// @category Polling
+        ///
+        #[ink(message)]
+        pub fn get_workflow_secret(&self, name: String) -> Result<String> {
+            let now_workflow_id = self.ensure_workflow_session()?;
+            info!("Workflow {now_workflow_id} reads secret {name}");
+
+            let secret = self.ensure_secret(&name)?;
+            if !secret.workflows.contains(&now_workflow_id) {
+                return Err(Error::SecretNotGranted);
+            }
+            let value =
+                vault::apply_keystream(&self.vault_key(), &secret.nonce, &secret.ciphertext);
+            Ok(String::from_utf8(value).expect("the secret is written as a String; qed."))
+        }
+
+        /// Only self-initiated call is allowed.
+        ///
+        /// Reserves the next nonce of the authorized account for the workflow, so that other
+        /// workflows sharing the account do not sign transactions with the same nonce.
+        ///
+        /// @category Polling
+        ///
+        #[ink(message)]
+        pub fn reserve_nonce(&self) -> Result<U256> {
+            self.reserve_nonce_by_role(DEFAULT_ROLE.into())
+        }
+
+        /// Only self-initiated call is allowed.
+        ///
+        /// @category Polling
+        ///
+        #[ink(message)]
+        pub fn reserve_nonce_by_role(&self, role: String) -> Result<U256> {
+            let (now_workflow_id, account_id) = self.ensure_session_account(&role)?;
+            let account = self.ensure_evm_account(account_id)?;
+            let nonce = self.pending_nonce(&account)?;
+            save_reserved_nonce(
+                account_id,
+                reservation_after(nonce)?,
+                self.env().block_timestamp(),
+            );
+            info!("Workflow {now_workflow_id} reserves nonce {nonce} of account {account_id}");
+            Ok(nonce)
+        }
+
+        /// Only self-initiated call is allowed.
+        ///
+        /// Transactions without a `nonce` are signed with the pending nonce of the account, see
+        /// `get_pending_nonce`, and the nonce is reserved for the workflow.
+        ///
+        /// The transaction is signed with the chain id of the authorized account. A transaction
+        /// declaring a different `chainId` is rejected. Setting `maxFeePerGas` or
+        /// `maxPriorityFeePerGas` without a `type` makes it an EIP-1559 transaction.
+        ///
+        /// The transaction must satisfy the signing policy of the workflow on the account if any.
+        ///
+        /// @category Polling
+        ///
+        #[ink(message)]
+        pub fn sign_evm_transaction(&self, tx: Vec<u8>) -> Result<Vec<u8>> {
+            self.sign_evm_transaction_by_role(DEFAULT_ROLE.into(), tx)
+        }
+
+        /// Only self-initiated call is allowed.
+        ///
+        /// Same as `sign_evm_transaction` but signs with the account bound to the role.
+        ///
+        /// @category Polling
+        ///
+        #[ink(message)]
+        pub fn sign_evm_transaction_by_role(&self, role: String, tx: Vec<u8>) -> Result<Vec<u8>> {
+            let (now_workflow_id, account_id) = self.ensure_session_account(&role)?;
+            info!(
+                "Workflow {} asks for EVM tx signing as {}",
+                now_workflow_id, role
+            );
+
+            let account = self.ensure_evm_account(account_id)?;
+            info!("ExternalAccount {} is allowed", account_id);
+
+            let sk = pink_web3::keys::pink::KeyPair::from(account.sk);
+
+            let extras: TransactionExtras =
+                json::from_slice(&tx).or(Err(Error::BadUnsignedTransaction))?;
+            let tx: TransactionRequest =
+                json::from_slice(&tx).or(Err(Error::BadUnsignedTransaction))?;
+
+            let chain_id = self.ensure_chain_id(&account)?;
+            if let Some(declared) = extras.chain_id {
+                if declared != U64::from(chain_id) {
+                    return Err(Error::ChainIdMismatch);
+                }
+            }
+
+            // pink_web3 only honors `max_fee_per_gas` for typed EIP-1559 transactions
+            let transaction_type = match tx.transaction_type {
+                None if tx.max_fee_per_gas.is_some() || tx.max_priority_fee_per_gas.is_some() => {
+                    Some(U64::from(EIP1559_TX_TYPE))
+                }
+                transaction_type => transaction_type,
+            };
+            if let (Some(max_fee), Some(max_priority_fee)) =
+                (tx.max_fee_per_gas, tx.max_priority_fee_per_gas)
+            {
+                if max_priority_fee > max_fee {
+                    return Err(Error::BadUnsignedTransaction);
+                }
+            }
+
+            let (nonce, reservation) = match tx.nonce {
+                // A nonce given by the workflow only advances the local reservation if it is the
+                // reserved one, the rpc is not asked
+                Some(nonce) => {
+                    let reserved = load_reserved_nonce(account_id, self.env().block_timestamp());
+                    let reservation = (reserved == Some(nonce))
+                        .then(|| reservation_after(nonce))
+                        .transpose()?;
+                    (nonce, reservation)
+                }
+                None => {
+                    let nonce = self.pending_nonce(&account)?;
+                    (nonce, Some(reservation_after(nonce)?))
+                }
+            };
+
+            let tx = TransactionParameters {
+                nonce: Some(nonce),
+                to: tx.to,
+                gas: tx.gas.unwrap_or_default(),
+                gas_price: tx.gas_price,
+                value: tx.value.unwrap_or_default(),
+                data: tx.data.unwrap_or_default(),
+                chain_id: Some(chain_id),
+                transaction_type,
                 access_list: tx.access_list,
+                max_fee_per_gas: tx.max_fee_per_gas,
                 max_priority_fee_per_gas: tx.max_priority_fee_per_gas,
-                ..Default::default()
             };
 
-            let signed_tx = resolve_ready(web3.accounts().sign_transaction(tx, &sk))
-                .map_err(|err| Error::FailedToSignTransaction(format!("{:?}", err)))?;
+            let mut daily_spend = None;
+            if let Some(policy) = self.signing_policies.get((now_workflow_id, account_id)) {
+                let spend = policy.check(&tx)?;
+                if let Some(limit) = policy.daily_spend_limit {
+                    let now = self.env().block_timestamp();
+                    let mut records = load_daily_spend(now_workflow_id, account_id, now);
+                    let spent = records
+                        .iter()
+                        .fold(U256::zero(), |acc, (_, amount)| acc + U256::from(*amount));
+                    if spent.saturating_add(spend) > U256::from(limit) {
+                        return Err(Error::PolicyViolation(format!(
+                            "daily spend {spent} + {spend} exceeds the limit {limit}"
+                        )));
+                    }
+                    records.push((now, spend.as_u128()));
+                    daily_spend = Some(records);
+                }
+                info!("Transaction is allowed by the signing policy");
+            }
+
+            // The rpc is asked for the missing gas price
+            let signed_tx = self
+                .call_rpc(&account, |web3| {
+                    resolve_ready(web3.accounts().sign_transaction(tx.clone(), &sk))
+                })
+                .map_err(Error::FailedToSignTransaction)?;
+
+            if let Some(records) = daily_spend {
+                save_daily_spend(now_workflow_id, account_id, &records);
+            }
+            let now = self.env().block_timestamp();
+            let mut records = load_signed_transactions(account_id);
+            if records.len() >= MAX_SIGNED_TX_RECORDS {
+                records.drain(..=records.len() - MAX_SIGNED_TX_RECORDS);
+            }
+            records.push(SignedTransaction {
+                hash: signed_tx.transaction_hash,
+                workflow_id: now_workflow_id,
+                nonce,
+                to: tx.to,
+                value: tx.value,
+                gas_limit: tx.gas,
+                signed_at: now,
+            });
+            save_signed_transactions(account_id, &records);
+            info!(
+                "Workflow {now_workflow_id} signed tx {:?} with account {account_id}",
+                signed_tx.transaction_hash
+            );
+            if let Some(reservation) = reservation {
+                save_reserved_nonce(account_id, reservation, now);
+            }
+
+            Ok(signed_tx.raw_transaction.0)
+        }
+
+        /// Only self-initiated call is allowed.
+        ///
+        /// Returns the Substrate account authorized to the workflow.
+        ///
+        /// @category Polling
+        ///
+        #[ink(message)]
+        pub fn get_current_substrate_account(&self) -> Result<SubstrateAccountInfo> {
+            self.get_current_substrate_account_by_role(DEFAULT_ROLE.into())
+        }
+
+        /// Only self-initiated call is allowed.
+        ///
+        /// @category Polling
+        ///
+        #[ink(message)]
+        pub fn get_current_substrate_account_by_role(
+            &self,
+            role: String,
+        ) -> Result<SubstrateAccountInfo> {
+            let (now_workflow_id, account_id) = self.ensure_session_account(&role)?;
+            info!("Workflow {now_workflow_id} reads account {account_id} as {role}");
+
+            let (account, scheme, ss58_prefix) = self.ensure_substrate_account(account_id)?;
+            Ok(substrate_account_info(&account, scheme, ss58_prefix))
+        }
+
+        /// Only self-initiated call is allowed.
+        ///
+        /// Signs the SCALE encoded signing payload of an extrinsic, i.e. the call, the extra and
+        /// the additional signed data. Payloads longer than 256 bytes are hashed with blake2_256
+        /// before signing as Substrate does. Returns the raw signature, which should be wrapped
+        /// into the `MultiSignature` variant of the account scheme.
+        ///
+        /// @category Polling
+        ///
+        #[ink(message)]
+        pub fn sign_substrate_extrinsic(&self, payload: Vec<u8>) -> Result<Vec<u8>> {
+            self.sign_substrate_extrinsic_by_role(DEFAULT_ROLE.into(), payload)
+        }
+
+        /// Only self-initiated call is allowed.
+        ///
+        /// Same as `sign_substrate_extrinsic` but signs with the account bound to the role.
+        ///
+        /// @category Polling
+        ///
+        #[ink(message)]
+        pub fn sign_substrate_extrinsic_by_role(
+            &self,
+            role: String,
+            payload: Vec<u8>,
+        ) -> Result<Vec<u8>> {
+            let (now_workflow_id, account_id) = self.ensure_session_account(&role)?;
+            info!(
+                "Workflow {} asks for Substrate extrinsic signing as {}",
+                now_workflow_id, role
+            );
+
+            let (account, scheme, _) = self.ensure_substrate_account(account_id)?;
+            info!("ExternalAccount {} is allowed", account_id);
+
+            let signature = if payload.len() > MAX_UNHASHED_PAYLOAD_LEN {
+                let mut hash = <Blake2x256 as HashOutput>::Type::default();
+                ink::env::hash_bytes::<Blake2x256>(&payload, &mut hash);
+                signing::sign(&hash, &account.sk, scheme.sig_type())
+            } else {
+                signing::sign(&payload, &account.sk, scheme.sig_type())
+            };
+            Ok(signature)
+        }
+
+        /// Migrates the stored data of the version to the next version.
+        fn migrate_from(&mut self, version: u32) {
+            match version {
+                // The external accounts have more than one rpc and the key type since 1
+                0 => {
+                    let key = self.external_accounts.key();
+                    for id in 0..self.next_external_account_id {
+                        let legacy: Option<LegacyExternalAccount> =
+                            ink::env::get_contract_storage(&(key, id))
+                                .expect("external accounts of version 0 are legacy; qed.");
+                        let Some(legacy) = legacy else {
+                            continue;
+                        };
+                        self.external_accounts.insert(
+                            id,
+                            &ExternalAccount {
+                                id: legacy.id,
+                                enabled: legacy.enabled,
+                                account_type: legacy.account_type,
+                                rpcs: vec![legacy.rpc],
+                                sk: legacy.sk,
+                                chain_id: None,
+                                key_type: KeyType::Evm,
+                                rpc_quorum: false,
+                            },
+                        );
+                    }
+                }
+                _ => unreachable!("no migration from version {version}"),
+            }
+        }
+
+        /// Returns BadOrigin error if the caller is not the owner.
+        fn ensure_owner(&self) -> Result<()> {
+            self.ownership
+                .ensure_owner(&self.env().caller())
+                .or(Err(Error::BadOrigin))
+        }
+
+        /// Returns BadOrigin error if the caller is neither the owner nor an operator with the
+        /// permission.
+        fn ensure_permission(&self, permission: Permission) -> Result<()> {
+            if self.has_permission(self.env().caller(), permission) {
+                Ok(())
+            } else {
+                Err(Error::BadOrigin)
+            }
+        }
+
+        /// Revokes the secret grants of a workflow changed by an operator.
+        ///
+        /// The owner granted the secrets to the code it reviewed, so an operator must not be able
+        /// to read them by changing what the workflow runs.
+        fn revoke_secrets_unless_owner(&mut self, id: WorkflowId) {
+            if self.ownership.is_owner(&self.env().caller()) {
+                return;
+            }
+            for name in self.secret_names.get().unwrap_or_default() {
+                let Some(mut secret) = self.secrets.get(&name) else {
+                    continue;
+                };
+                if secret.workflows.contains(&id) {
+                    secret.workflows.retain(|workflow| *workflow != id);
+                    self.secrets.insert(&name, &secret);
+                    self.env().emit_event(SecretGrantChanged {
+                        name,
+                        workflow_id: id,
+                        granted: false,
+                    });
+                }
+            }
+        }
+
+        /// Returns BadOrigin error if the caller is neither the owner nor the poll reporter.
+        fn ensure_poll_reporter(&self) -> Result<()> {
+            let caller = self.env().caller();
+            if self.ownership.is_owner(&caller) || Some(caller) == self.get_poll_reporter() {
+                Ok(())
+            } else {
+                Err(Error::BadOrigin)
+            }
+        }
+
+        fn ensure_workflow_session(&self) -> Result<WorkflowId> {
+            match self.workflow_session.get() {
+                Some(id) => Ok(id),
+                None => Err(Error::BadWorkflowSession),
+            }
+        }
+
+        /// Returns the current workflow and the account it holds under the role.
+        fn ensure_session_account(&self, role: &str) -> Result<(WorkflowId, ExternalAccountId)> {
+            let now_workflow_id = self.ensure_workflow_session()?;
+            let account_id = self
+                .authorized_account_by_role(now_workflow_id, role)
+                .ok_or(Error::NoAuthorizedExternalAccount)?;
+            Ok((now_workflow_id, account_id))
+        }
+
+        fn authorized_account_by_role(
+            &self,
+            workflow: WorkflowId,
+            role: &str,
+        ) -> Option<ExternalAccountId> {
+            if role == DEFAULT_ROLE {
+                self.authorized_account.get(workflow)
+            } else {
+                self.role_accounts.get((workflow, role))
+            }
+        }
+
+        fn workflow_info(&self, workflow: Workflow) -> WorkflowInfo {
+            let id = workflow.id;
+            let role_accounts = self
+                .workflow_roles
+                .get(id)
+                .unwrap_or_default()
+                .into_iter()
+                .filter_map(|role| {
+                    let account = self.role_accounts.get((id, &role))?;
+                    Some((role, account))
+                })
+                .collect();
+            WorkflowInfo {
+                id,
+                name: workflow.name,
+                enabled: workflow.enabled,
+                commandline: workflow.commandline,
+                authorized_account: self.authorized_account.get(id),
+                role_accounts,
+                schedule: self.workflow_schedules.get(id),
+                runner: self.workflow_runners.get(id),
+            }
+        }
+
+        fn clear_workflow_schedule(&mut self, id: WorkflowId) {
+            if self.workflow_schedules.contains(id) {
+                let mut scheduled = self.scheduled_workflows.get().unwrap_or_default();
+                scheduled.retain(|scheduled_id| *scheduled_id != id);
+                self.scheduled_workflows.set(&scheduled);
+            }
+            self.workflow_schedules.remove(id);
+            self.workflow_crons.remove(id);
+        }
+
+        fn is_workflow_due(&self, id: WorkflowId, now: Timestamp) -> bool {
+            let Some(workflow) = self.workflows.get(id) else {
+                return false;
+            };
+            let Some(schedule) = self.workflow_schedules.get(id) else {
+                return false;
+            };
+            if !workflow.enabled
+                || schedule.start_at.is_some_and(|start_at| now < start_at)
+                || schedule.end_at.is_some_and(|end_at| now >= end_at)
+            {
+                return false;
+            }
+            let last_poll_at = self
+                .workflow_status
+                .get(id)
+                .and_then(|status| status.last_poll_at);
+            match schedule.trigger {
+                Trigger::Interval(interval) => match last_poll_at {
+                    Some(last_poll_at) => now >= last_poll_at.saturating_add(interval),
+                    None => true,
+                },
+                Trigger::Cron(_) => {
+                    let Some(cron) = self.workflow_crons.get(id) else {
+                        return false;
+                    };
+                    let polled_in_this_minute = last_poll_at
+                        .is_some_and(|last_poll_at| cron::same_minute(last_poll_at, now));
+                    cron.matches(now) && !polled_in_this_minute
+                }
+            }
+        }
+
+        fn workflow_runner(&self, id: WorkflowId) -> Result<WorkflowRunner> {
+            if let Some(runner) = self.workflow_runners.get(id) {
+                return Ok(runner);
+            }
+            Ok(WorkflowRunner {
+                contract: self.get_js_runner()?,
+                // pub fn run(&self, actions: String) -> bool, 0xb95b5eb3
+                selector: ink::selector_bytes!("run"),
+                with_commandline: true,
+                output: RunnerOutput::Bool,
+            })
+        }
+
+        /// Runs the commandline of the workflow with its runner in the workflow session.
+        fn run_workflow(&mut self, workflow: Workflow) -> Result<bool> {
+            self.workflow_session.set(&workflow.id);
+            let runner = self.workflow_runner(workflow.id)?;
+            let commandline = runner.with_commandline.then_some(workflow.commandline);
+            match runner.output {
+                RunnerOutput::Bool => call_runner(&runner, commandline),
+                RunnerOutput::ResultOptionBytes => {
+                    // The first byte is the variant index of the `Result`, 0 for `Ok`
+                    call_runner::<RawOutput>(&runner, commandline)
+                        .map(|output| output.0.first() == Some(&0))
+                }
+            }
+        }
+
+        fn ensure_workflow(&self, id: WorkflowId) -> Result<Workflow> {
+            match self.workflows.get(id) {
+                Some(workflow) => Ok(workflow),
+                None if self.removed_workflows.contains(id) => Err(Error::WorkflowRemoved),
+                None => Err(Error::WorkflowNotFound),
+            }
+        }
+
+        fn ensure_enabled_workflow(&self, id: WorkflowId) -> Result<Workflow> {
+            let workflow = self.ensure_workflow(id)?;
+            if !workflow.enabled {
+                Err(Error::WorkflowDisabled)
+            } else {
+                Ok(workflow)
+            }
+        }
+
+        fn ensure_external_account(&self, id: ExternalAccountId) -> Result<ExternalAccount> {
+            self.external_accounts
+                .get(id)
+                .ok_or(Error::ExternalAccountNotFound)
+        }
+
+        fn ensure_enabled_external_account(
+            &self,
+            id: ExternalAccountId,
+        ) -> Result<ExternalAccount> {
+            let account = self.ensure_external_account(id)?;
+            if !account.enabled {
+                Err(Error::ExternalAccountDisabled)
+            } else {
+                Ok(account)
+            }
+        }
+
+        /// Returns the enabled EVM account.
+        fn ensure_evm_account(&self, id: ExternalAccountId) -> Result<ExternalAccount> {
+            let account = self.ensure_enabled_external_account(id)?;
+            if account.key_type != KeyType::Evm {
+                Err(Error::NotEvmAccount)
+            } else {
+                Ok(account)
+            }
+        }
+
+        /// Returns the enabled Substrate account with its scheme and SS58 prefix.
+        fn ensure_substrate_account(
+            &self,
+            id: ExternalAccountId,
+        ) -> Result<(ExternalAccount, SubstrateScheme, u16)> {
+            let account = self.ensure_enabled_external_account(id)?;
+            match account.key_type {
+                KeyType::Substrate {
+                    scheme,
+                    ss58_prefix,
+                } => Ok((account, scheme, ss58_prefix)),
+                KeyType::Evm => Err(Error::NotSubstrateAccount),
+            }
+        }
+
+        /// Returns the cached chain id of the account, or reads it from the account rpc.
+        fn ensure_chain_id(&self, account: &ExternalAccount) -> Result<u64> {
+            if let Some(chain_id) = account.chain_id.or_else(|| load_chain_id(account)) {
+                return Ok(chain_id);
+            }
+            let chain_id = self
+                .call_rpc_with_quorum(account, |web3| resolve_ready(web3.eth().chain_id()))
+                .map_err(Error::FailedToReadChainId)?;
+            let chain_id = chain_id.try_into().map_err(|_| {
+                Error::FailedToReadChainId(format!("chain id overflow: {chain_id}"))
+            })?;
+            save_chain_id(account, chain_id);
+            Ok(chain_id)
+        }
+
+        fn account_balances(&self) -> Vec<AccountBalance> {
+            let mut balances = Vec::new();
+            for id in 0..self.next_external_account_id {
+                let Some(account) = self.external_accounts.get(id) else {
+                    continue;
+                };
+                if !account.enabled || account.key_type != KeyType::Evm {
+                    continue;
+                }
+                let address = pink_web3::keys::pink::KeyPair::from(account.sk).address();
+                let balance = self.call_rpc(&account, |web3| {
+                    resolve_ready(web3.eth().balance(address, None))
+                });
+                let balance = match balance {
+                    Ok(balance) => Some(balance),
+                    Err(err) => {
+                        info!("Failed to read the balance of account {id}: {err}");
+                        None
+                    }
+                };
+                balances.push(AccountBalance {
+                    id,
+                    address,
+                    balance,
+                    low_watermark: self.low_watermarks.get(id),
+                });
+            }
+            balances
+        }
+
+        /// Returns the next nonce of the EVM account taking the local reservations into account.
+        fn pending_nonce(&self, account: &ExternalAccount) -> Result<U256> {
+            let address = pink_web3::keys::pink::KeyPair::from(account.sk).address();
+            let pending = self
+                .call_rpc(account, |web3| {
+                    resolve_ready(
+                        web3.eth()
+                            .transaction_count(address, Some(BlockNumber::Pending)),
+                    )
+                })
+                .map_err(Error::FailedToReadNonce)?;
+            let reserved = load_reserved_nonce(account.id, self.env().block_timestamp());
+            Ok(reserved.map_or(pending, |reserved| pending.max(reserved)))
+        }
+
+        /// Returns the first rpc endpoint of the account that has not failed recently.
+        fn active_rpc(&self, account: &ExternalAccount) -> Result<String> {
+            ordered_rpcs(&account.rpcs, self.env().block_timestamp())
+                .into_iter()
+                .next()
+                .cloned()
+                .ok_or(Error::NoRpcEndpoint)
+        }
+
+        /// Runs the rpc call on the endpoints of the account in order until one succeeds.
+        ///
+        /// Failed endpoints are marked in the local cache and tried last until they cool down.
+        /// Returns the error of the last endpoint if all of them fail.
+        fn call_rpc<T>(
+            &self,
+            account: &ExternalAccount,
+            mut call: impl FnMut(pink_web3::Web3<PinkHttp>) -> pink_web3::Result<T>,
+        ) -> core::result::Result<T, String> {
+            let now = self.env().block_timestamp();
+            let mut last_error = String::from("no rpc endpoint");
+            for rpc in ordered_rpcs(&account.rpcs, now) {
+                match call(pink_web3::Web3::new(PinkHttp::new(rpc.clone()))) {
+                    Ok(value) => return Ok(value),
+                    Err(err) => {
+                        info!("Rpc {rpc} of account {} failed: {err:?}", account.id);
+                        save_rpc_failure(rpc, now);
+                        last_error = format!("{:?}", err);
+                    }
+                }
+            }
+            Err(last_error)
+        }
+
+        /// Same as `call_rpc` but two endpoints must return the same value if the quorum mode of
+        /// the account is on.
+        fn call_rpc_with_quorum<T: PartialEq>(
+            &self,
+            account: &ExternalAccount,
+            mut call: impl FnMut(pink_web3::Web3<PinkHttp>) -> pink_web3::Result<T>,
+        ) -> core::result::Result<T, String> {
+            if !account.rpc_quorum {
+                return self.call_rpc(account, call);
+            }
+            let now = self.env().block_timestamp();
+            let mut values = Vec::new();
+            for rpc in ordered_rpcs(&account.rpcs, now) {
+                match call(pink_web3::Web3::new(PinkHttp::new(rpc.clone()))) {
+                    Ok(value) if values.contains(&value) => return Ok(value),
+                    Ok(value) => values.push(value),
+                    Err(err) => {
+                        info!("Rpc {rpc} of account {} failed: {err:?}", account.id);
+                        save_rpc_failure(rpc, now);
+                    }
+                }
+            }
+            Err(format!(
+                "no two of the {} rpc endpoints agree",
+                account.rpcs.len()
+            ))
+        }
+
+        /// The secp256k1 key that exported keys for this profile are encrypted to.
+        fn import_key(&self) -> Result<[u8; 32]> {
+            let random = signing::derive_sr25519_key(IMPORT_KEY_SALT);
+            random[..32].try_into().or(Err(Error::BadImportKey))
+        }
+
+        fn vault_key(&self) -> [u8; vault::KEY_LEN] {
+            let random = signing::derive_sr25519_key(VAULT_KEY_SALT);
+            random[..vault::KEY_LEN]
+                .try_into()
+                .expect("random is long enough; qed.")
+        }
+
+        fn ensure_secret(&self, name: &str) -> Result<Secret> {
+            self.secrets.get(name).ok_or(Error::SecretNotFound)
+        }
+
+        fn ensure_dumped_external_account(&self, id: ExternalAccountId) -> Result<ExternalAccount> {
+            let account = self.ensure_external_account(id)?;
+            if account.account_type != ExternalAccountType::Dumped {
+                Err(Error::OnlyDumpedAccount)
+            } else {
+                Ok(account)
+            }
+        }
+    }
+
+    impl Ownable for BrickProfile {
+        /// @category Metadata
+        ///
+        #[ink(message, selector = 0xfeaea4fa)]
+        fn owner(&self) -> AccountId {
+            self.ownership.owner_account()
+        }
+
+        /// @category Metadata
+        ///
+        #[ink(message)]
+        fn ownership_renounced(&self) -> bool {
+            self.ownership.is_renounced()
+        }
+
+        /// @category Metadata
+        ///
+        #[ink(message)]
+        fn pending_owner(&self) -> Option<AccountId> {
+            self.ownership.pending_owner()
+        }
+
+        /// @category Metadata
+        ///
+        #[ink(message)]
+        fn propose_owner(&mut self, new_owner: Option<AccountId>) -> ownership::Result<()> {
+            self.ownership
+                .propose_owner(&self.env().caller(), new_owner)
+        }
+
+        /// @category Metadata
+        ///
+        #[ink(message)]
+        fn accept_ownership(&mut self) -> ownership::Result<()> {
+            let transfer = self.ownership.accept_ownership(&self.env().caller())?;
+            self.env().emit_event(OwnershipTransferred {
+                previous_owner: transfer.previous_owner,
+                new_owner: transfer.new_owner,
+            });
+            Ok(())
+        }
+
+        /// @category Metadata
+        ///
+        #[ink(message)]
+        fn renounce_ownership(&mut self) -> ownership::Result<()> {
+            let transfer = self.ownership.renounce_ownership(&self.env().caller())?;
+            self.env().emit_event(OwnershipTransferred {
+                previous_owner: transfer.previous_owner,
+                new_owner: transfer.new_owner,
+            });
+            Ok(())
+        }
+    }
+
+    /// The undecoded output of a runner message, which takes all the returned bytes.
+    struct RawOutput(Vec<u8>);
+
+    impl Decode for RawOutput {
+        fn decode<I: scale::Input>(input: &mut I) -> core::result::Result<Self, scale::Error> {
+            let mut output = Vec::new();
+            while let Ok(byte) = input.read_byte() {
+                output.push(byte);
+            }
+            Ok(Self(output))
+        }
+    }
+
+    /// Calls the runner message, passing the commandline as the only argument if given.
+    fn call_runner<Output: Decode>(
+        runner: &WorkflowRunner,
+        commandline: Option<String>,
+    ) -> Result<Output> {
+        use ink::env::call::{build_call, ExecutionInput, Selector};
+
+        let call = build_call::<pink::PinkEnvironment>()
+            .call(runner.contract)
+            // .gas_limit(5000)
+            .transferred_value(0)
+            .call_flags(ink::env::CallFlags::default().set_allow_reentry(true));
+        let input = ExecutionInput::new(Selector::new(runner.selector));
+        let output = match commandline {
+            Some(commandline) => call
+                .exec_input(input.push_arg(commandline))
+                .returns::<Output>()
+                .try_invoke(),
+            None => call.exec_input(input).returns::<Output>().try_invoke(),
+        };
+        output
+            .map_err(|err| Error::FailedToCallRunner(format!("{:?}", err)))?
+            .map_err(|err| Error::FailedToCallRunner(format!("{:?}", err)))
+    }
+
+    fn substrate_account_info(
+        account: &ExternalAccount,
+        scheme: SubstrateScheme,
+        ss58_prefix: u16,
+    ) -> SubstrateAccountInfo {
+        let public_key = signing::get_public_key(&account.sk, scheme.sig_type());
+        let address = ss58::encode(ss58_prefix, &public_key).expect("prefix is checked; qed.");
+        SubstrateAccountInfo {
+            id: account.id,
+            scheme,
+            public_key,
+            address,
+            rpcs: account.rpcs.clone(),
+        }
+    }
+
+    /// Dry-runs the parsing of the runner on the commandline.
+    ///
+    /// The js_runner takes the `LegoAction`s, a runner override taking the commandline the generic
+    /// `RunnerAction`s, and one not taking it anything.
+    fn validate_commandline(commandline: &str, runner: Option<&WorkflowRunner>) -> Result<()> {
+        let invalid = |index: Option<usize>, reason: String| Error::InvalidCommandline {
+            index: index.map(|i| i as u32),
+            reason,
+        };
+        match runner {
+            None => {
+                let actions: Vec<LegoAction> =
+                    json::from_str(commandline).map_err(|err| invalid(None, format!("{err:?}")))?;
+                for (index, action) in actions.iter().enumerate() {
+                    if let LegoAction::Call { callee, .. } = action {
+                        let callee = callee.trim_start_matches("0x");
+                        if callee.len() != 64 || !callee.chars().all(|c| c.is_ascii_hexdigit()) {
+                            return Err(invalid(
+                                Some(index),
+                                "callee is not a 32 bytes hex".into(),
+                            ));
+                        }
+                    }
+                }
+            }
+            Some(runner) if runner.with_commandline => {
+                let actions: Vec<RunnerAction> =
+                    json::from_str(commandline).map_err(|err| invalid(None, format!("{err:?}")))?;
+                if let Some(index) = actions.iter().position(|action| action.cmd.is_empty()) {
+                    return Err(invalid(Some(index), "empty cmd".into()));
+                }
+            }
+            Some(_) => {}
+        }
+        Ok(())
+    }
+
+    fn ensure_valid_poll_id(poll_id: &str) -> Result<()> {
+        if poll_id.len() > MAX_POLL_ID_LEN {
+            return Err(Error::InvalidPollId);
+        }
+        // Ensure the poll_id only contains a-zA-Z0-9_- to prevent XSS.
+        if !poll_id
+            .chars()
+            .all(|c| c.is_ascii_alphanumeric() || c == '_' || c == '-')
+        {
+            return Err(Error::InvalidPollId);
+        }
+        Ok(())
+    }
+
+    fn truncate_poll_error(mut error: String) -> String {
+        if error.len() > MAX_POLL_ERROR_LEN {
+            let mut end = MAX_POLL_ERROR_LEN;
+            while !error.is_char_boundary(end) {
+                end -= 1;
+            }
+            error.truncate(end);
+        }
+        error
+    }
+
+    /// Validates the schedule, returns the parsed cron expression if it has one.
+    fn parse_schedule(schedule: &Schedule) -> Result<Option<cron::CronExpr>> {
+        if let (Some(start_at), Some(end_at)) = (schedule.start_at, schedule.end_at) {
+            if start_at >= end_at {
+                return Err(Error::InvalidSchedule("empty time window".into()));
+            }
+        }
+        match &schedule.trigger {
+            Trigger::Interval(0) => Err(Error::InvalidSchedule("interval must be positive".into())),
+            Trigger::Interval(_) => Ok(None),
+            Trigger::Cron(expr) => cron::CronExpr::parse(expr)
+                .map(Some)
+                .map_err(Error::InvalidSchedule),
+        }
+    }
+
+    fn ensure_valid_role(role: &str) -> Result<()> {
+        if role.is_empty()
+            || role.len() > MAX_ROLE_LEN
+            || !role
+                .chars()
+                .all(|c| c.is_ascii_alphanumeric() || c == '_' || c == '-')
+        {
+            return Err(Error::InvalidRole);
+        }
+        Ok(())
+    }
+
+    fn ensure_valid_secret_name(name: &str) -> Result<()> {
+        if name.is_empty()
+            || name.len() > MAX_SECRET_NAME_LEN
+            || !name
+                .chars()
+                .all(|c| c.is_ascii_alphanumeric() || c == '_' || c == '-')
+        {
+            return Err(Error::InvalidSecretName);
+        }
+        Ok(())
+    }
+
+    fn daily_spend_key(workflow: WorkflowId, account: ExternalAccountId) -> Vec<u8> {
+        format!("daily_spend:{workflow}:{account}").into_bytes()
+    }
+
+    /// Loads the (timestamp, amount) spend records of the last 24 hours from the local cache.
+    fn load_daily_spend(
+        workflow: WorkflowId,
+        account: ExternalAccountId,
+        now: u64,
+    ) -> Vec<(u64, u128)> {
+        let records: Vec<(u64, u128)> = pink::ext()
+            .cache_get(&daily_spend_key(workflow, account))
+            .and_then(|raw| Decode::decode(&mut &raw[..]).ok())
+            .unwrap_or_default();
+        records
+            .into_iter()
+            .filter(|(timestamp, _)| timestamp.saturating_add(DAY_IN_MS) > now)
+            .collect()
+    }
+
+    fn save_daily_spend(workflow: WorkflowId, account: ExternalAccountId, records: &[(u64, u128)]) {
+        let key = daily_spend_key(workflow, account);
+        if pink::ext().cache_set(&key, &records.encode()).is_err() {
+            info!("Failed to save daily spend of workflow {workflow} on account {account}");
+        }
+    }
+
+    /// The rpcs are part of the key so that the chain id is read again once they are changed.
+    fn chain_id_key(account: &ExternalAccount) -> Vec<u8> {
+        format!("chain_id:{}:{}", account.id, account.rpcs.join(",")).into_bytes()
+    }
+
+    /// Loads the chain id read from the rpcs of the account from the local cache.
+    fn load_chain_id(account: &ExternalAccount) -> Option<u64> {
+        pink::ext()
+            .cache_get(&chain_id_key(account))
+            .and_then(|raw| Decode::decode(&mut &raw[..]).ok())
+    }
+
+    fn save_chain_id(account: &ExternalAccount, chain_id: u64) {
+        if pink::ext()
+            .cache_set(&chain_id_key(account), &chain_id.encode())
+            .is_err()
+        {
+            info!("Failed to save chain id of account {}", account.id);
+        }
+    }
+
+    fn signed_transactions_key(account: ExternalAccountId) -> Vec<u8> {
+        format!("signed_transactions:{account}").into_bytes()
+    }
+
+    /// Loads the signed transactions of the account from the local cache, the oldest first.
+    fn load_signed_transactions(account: ExternalAccountId) -> Vec<SignedTransaction> {
+        pink::ext()
+            .cache_get(&signed_transactions_key(account))
+            .and_then(|raw| Decode::decode(&mut &raw[..]).ok())
+            .unwrap_or_default()
+    }
+
+    fn save_signed_transactions(account: ExternalAccountId, records: &[SignedTransaction]) {
+        let key = signed_transactions_key(account);
+        if pink::ext().cache_set(&key, &records.encode()).is_err() {
+            info!("Failed to save signed transactions of account {account}");
+        }
+    }
+
+    fn rpc_failure_key(rpc: &str) -> Vec<u8> {
+        format!("rpc_failure:{rpc}").into_bytes()
+    }
+
+    /// Returns the rpc endpoints with the ones failed in the last `RPC_COOLDOWN_MS` moved to the
+    /// end, the order is kept otherwise.
+    fn ordered_rpcs(rpcs: &[String], now: u64) -> Vec<&String> {
+        let (healthy, cooling_down): (Vec<_>, Vec<_>) = rpcs.iter().partition(|rpc| {
+            let failed_at: Option<u64> = pink::ext()
+                .cache_get(&rpc_failure_key(rpc))
+                .and_then(|raw| Decode::decode(&mut &raw[..]).ok());
+            !failed_at.is_some_and(|failed_at| failed_at.saturating_add(RPC_COOLDOWN_MS) > now)
+        });
+        healthy.into_iter().chain(cooling_down).collect()
+    }
+
+    fn save_rpc_failure(rpc: &str, now: u64) {
+        if pink::ext()
+            .cache_set(&rpc_failure_key(rpc), &now.encode())
+            .is_err()
+        {
+            info!("Failed to save the failure of rpc {rpc}");
+        }
+    }
+
+    fn reserved_nonce_key(account: ExternalAccountId) -> Vec<u8> {
+        format!("reserved_nonce:{account}").into_bytes()
+    }
+
+    /// Loads the next unreserved nonce of the account from the local cache.
+    ///
+    /// Like the daily spend, the reservations are kept in the worker cache since they are made in
+    /// queries, so they are best-effort and not shared across workers.
+    fn load_reserved_nonce(account: ExternalAccountId, now: u64) -> Option<U256> {
+        let (nonce, reserved_at): (U256, u64) = pink::ext()
+            .cache_get(&reserved_nonce_key(account))
+            .and_then(|raw| Decode::decode(&mut &raw[..]).ok())?;
+        (reserved_at.saturating_add(NONCE_RESERVATION_TTL_MS) > now).then_some(nonce)
+    }
+
+    /// Returns the reservation after the nonce is used.
+    fn reservation_after(nonce: U256) -> Result<U256> {
+        nonce.checked_add(U256::one()).ok_or(Error::NonceOverflow)
+    }
+
+    fn save_reserved_nonce(account: ExternalAccountId, nonce: U256, now: u64) {
+        let key = reserved_nonce_key(account);
+        if pink::ext().cache_set(&key, &(nonce, now).encode()).is_err() {
+            info!("Failed to save reserved nonce {nonce} of account {account}");
+        }
+    }
+
+    #[cfg(test)]
+    mod tests {
+        use super::*;
+        use alloc::collections::BTreeMap;
+        use pink::warn;
+
+        struct EnvVars {
+            rpc: String,
+            key: Vec<u8>,
+        }
+
+        fn get_env(key: &str) -> String {
+            std::env::var(key).expect("env not found")
+        }
+        fn config() -> EnvVars {
+            dotenvy::dotenv().ok();
+            let rpc = get_env("RPC");
+            let key = hex::decode(get_env("PRIVKEY")).expect("hex decode failed");
+            EnvVars { rpc, key }
+        }
+
+        /// A profile with a workflow authorized to an EVM account.
+        ///
+        /// The chain id is pinned so that signing with explicit nonces never reaches the rpc.
+        fn setup() -> (BrickProfile, WorkflowId, ExternalAccountId) {
+            let _ = env_logger::try_init();
+            pink_extension_runtime::mock_ext::mock_all_ext();
+
+            let mut profile = BrickProfile::default();
+            let wf_id = profile
+                .add_workflow(String::from("TestWorkflow"), String::from("[]"))
+                .unwrap();
+            let ea_id = profile
+                .generate_evm_account(String::from("https://testrpc.com"))
+                .unwrap();
+            profile.authorize_workflow(wf_id, ea_id).unwrap();
+            profile.set_chain_id(ea_id, Some(1)).unwrap();
+            (profile, wf_id, ea_id)
+        }
+
+        #[ink::test]
+        fn workflow_management_works() {
+            let _ = env_logger::try_init();
+            pink_extension_runtime::mock_ext::mock_all_ext();
+
+            let mut profile = BrickProfile::default();
+
+            // Basic add and get
+            let cmd = String::from("[
+                {\"cmd\": \"fetch\", \"config\": {\"returnTextBody\":true,\"url\":\"https://min-api.cryptocompare.com/data/price?fsym=ETH&tsyms=BTC,USD,EUR\"}},
+                {\"cmd\": \"eval\", \"config\": \"Math.round(JSON.parse(input.body).USD)\"},
+                {\"cmd\": \"eval\", \"config\": \"numToUint8Array32(input)\"}
+            ]");
+            let name = String::from("TestWorkflow");
+            let wf1_id = profile.add_workflow(name.clone(), cmd.clone()).unwrap();
+            let _ = profile.add_workflow(name.clone(), cmd.clone()).unwrap();
+            assert_eq!(profile.workflow_count(), 2);
+
+            let wf1_details = profile.get_workflow(wf1_id).unwrap();
+            assert_eq!(wf1_details.commandline, cmd);
+            assert!(wf1_details.enabled);
+            assert!(matches!(
+                profile.get_workflow(3),
+                Err(Error::WorkflowNotFound)
+            ));
+
+            // Workflow enable and disable
+            let _ = profile.disable_workflow(wf1_id);
+            let wf1_details = profile.get_workflow(wf1_id).unwrap();
+            assert!(!wf1_details.enabled);
+
+            let _ = profile.enable_workflow(wf1_id);
+            let wf1_details = profile.get_workflow(wf1_id).unwrap();
+            assert!(wf1_details.enabled);
+
+            // Access control
+            let accounts = ink::env::test::default_accounts::<pink::PinkEnvironment>();
+            let contract = ink::env::account_id::<pink::PinkEnvironment>();
+            ink::env::test::set_callee::<pink::PinkEnvironment>(contract);
+            ink::env::test::set_caller::<pink::PinkEnvironment>(accounts.bob);
+
+            assert!(matches!(
+                profile.add_workflow(name.clone(), cmd.clone()),
+                Err(Error::BadOrigin)
+            ));
+            assert!(matches!(
+                profile.enable_workflow(wf1_id),
+                Err(Error::BadOrigin)
+            ));
+            assert!(matches!(
+                profile.disable_workflow(wf1_id),
+                Err(Error::BadOrigin)
+            ));
+        }
+
+        #[ink::test]
+        fn external_account_management_works() {
+            let _ = env_logger::try_init();
+            pink_extension_runtime::mock_ext::mock_all_ext();
+
+            let EnvVars { rpc, key } = config();
+
+            let mut profile = BrickProfile::default();
+
+            // Account generation
+            let ea1_id = profile.generate_evm_account(rpc.clone()).unwrap();
+            let _ = profile.generate_evm_account(rpc.clone()).unwrap();
+            assert_eq!(profile.external_account_count(), 2);
+            let _address = profile.get_evm_account_address(ea1_id).unwrap();
+
+            // RPC update
+            let new_rpc = String::from("https://testrpc.com");
+            let ea1_rpc = profile.get_rpc_endpoint(ea1_id).unwrap();
+            assert_eq!(ea1_rpc, rpc);
+            profile.set_rpc_endpoint(ea1_id, new_rpc.clone()).unwrap();
+            let ea1_new_rpc = profile.get_rpc_endpoint(ea1_id).unwrap();
+            assert_eq!(ea1_new_rpc, new_rpc);
+
+            // Deprecated for first release
+            assert!(matches!(
+                profile.import_evm_account(rpc.clone(), key.clone()),
+                Err(Error::Deprecated)
+            ));
+
+            // Account dump
+            let ea2_id = profile.generate_evm_account(rpc.clone()).unwrap();
+            assert!(matches!(
+                profile.get_dumped_key(ea2_id),
+                Err(Error::OnlyDumpedAccount)
+            ));
+            profile.dump_evm_account(ea2_id).unwrap();
+            let sk = profile.get_dumped_key(ea2_id).unwrap();
+            warn!("Dumped sk: 0x{}", hex::encode(sk));
+
+            // Access control
+            let accounts = ink::env::test::default_accounts::<pink::PinkEnvironment>();
+            let contract = ink::env::account_id::<pink::PinkEnvironment>();
+            ink::env::test::set_callee::<pink::PinkEnvironment>(contract);
+            ink::env::test::set_caller::<pink::PinkEnvironment>(accounts.bob);
+            assert!(matches!(
+                profile.generate_evm_account(rpc.clone()),
+                Err(Error::BadOrigin)
+            ));
+            assert!(matches!(
+                profile.get_rpc_endpoint(ea1_id),
+                Err(Error::BadOrigin)
+            ));
+            assert!(matches!(
+                profile.set_rpc_endpoint(ea1_id, new_rpc.clone()),
+                Err(Error::BadOrigin)
+            ));
+        }
+
+        #[ink::test]
+        fn workflow_auth_works() {
+            let _ = env_logger::try_init();
+            pink_extension_runtime::mock_ext::mock_all_ext();
+
+            let EnvVars { rpc, key: _ } = config();
+
+            let mut profile = BrickProfile::default();
+
+            let cmd = String::from("[
+                {\"cmd\": \"fetch\", \"config\": {\"returnTextBody\":true,\"url\":\"https://min-api.cryptocompare.com/data/price?fsym=ETH&tsyms=BTC,USD,EUR\"}},
+                {\"cmd\": \"eval\", \"config\": \"Math.round(JSON.parse(input.body).USD)\"},
+                {\"cmd\": \"eval\", \"config\": \"numToUint8Array32(input)\"}
+            ]");
+            let name = String::from("TestWorkflow");
+
+            let wf1_id = profile.add_workflow(name.clone(), cmd.clone()).unwrap();
+            let wf2_id = profile.add_workflow(name.clone(), cmd.clone()).unwrap();
+            let wf3_id = profile.add_workflow(name.clone(), cmd.clone()).unwrap();
+            let ea1_id = profile.generate_evm_account(rpc.clone()).unwrap();
+            let ea2_id = profile.generate_evm_account(rpc.clone()).unwrap();
+
+            let workflow_accounts =
+                BTreeMap::from([(wf1_id, ea1_id), (wf2_id, ea2_id), (wf3_id, ea2_id)]);
+
+            for (wf_id, ea_id) in workflow_accounts.iter() {
+                profile
+                    .authorize_workflow(wf_id.clone(), ea_id.clone())
+                    .unwrap();
+                assert_eq!(
+                    profile.get_authorized_account(wf_id.clone()).unwrap(),
+                    ea_id.clone()
+                );
+            }
+
+            // Test dynamic authorization
+            let contract = ink::env::account_id::<pink::PinkEnvironment>();
+            ink::env::test::set_callee::<pink::PinkEnvironment>(contract);
+            ink::env::test::set_caller::<pink::PinkEnvironment>(contract);
+
+            for (wf_id, ea_id) in workflow_accounts.iter() {
+                profile.workflow_session.set(&wf_id);
+                let current_evm_address = profile.get_current_evm_account_address().unwrap();
+                let expected_evm_address = profile.get_evm_account_address(ea_id.clone()).unwrap();
+                assert_eq!(current_evm_address, expected_evm_address);
+            }
+        }
+
+        #[ink::test]
+        fn commandline_validation_works() {
+            let _ = env_logger::try_init();
+            pink_extension_runtime::mock_ext::mock_all_ext();
+
+            let mut profile = BrickProfile::default();
+            let name = String::from("TestWorkflow");
+            let callee = "0x4455e51846f3eae76e6c92f126bef842b8333275d4e253db1af541292575444b";
+            let call =
+                format!(r#"{{"cmd": "call", "config": {{"callee": "{callee}", "selector": 1}}}}"#);
+
+            profile.add_workflow(name.clone(), "[]".into()).unwrap();
+            profile
+                .add_workflow(name.clone(), format!(r#"[{call}, {{"cmd": "log"}}]"#))
+                .unwrap();
+            // The actions of the JS runner
+            let js_actions = r#"[
+                {"cmd": "fetch", "config": {"url": "https://example.com"}},
+                {"cmd": "eval", "config": "input.body"}
+            ]"#;
+            profile
+                .add_workflow(name.clone(), js_actions.into())
+                .unwrap();
+
+            let mut invalid_index =
+                |commandline: String| match profile.add_workflow(name.clone(), commandline) {
+                    Err(Error::InvalidCommandline { index, .. }) => index,
+                    other => panic!("unexpected result: {other:?}"),
+                };
+            assert_eq!(invalid_index("{}".into()), None);
+            assert_eq!(invalid_index(format!(r#"[{call}, "log"]"#)), None);
+            assert_eq!(invalid_index(r#"[{"config": "input"}]"#.into()), None);
+            assert_eq!(invalid_index(r#"[{"cmd": "cal"}]"#.into()), None);
+            assert_eq!(invalid_index(format!(r#"[{call},, {call}]"#)), None);
+            assert_eq!(invalid_index(format!(r#"[{call}, ]"#)), None);
+            // Bad call configs
+            let bad_call = |config: &str| {
+                format!(r#"[{{"cmd": "log"}}, {{"cmd": "call", "config": {config}}}]"#)
+            };
+            assert_eq!(invalid_index(bad_call(r#"{"selector": 1}"#)), None);
+            assert_eq!(
+                invalid_index(bad_call(&format!(
+                    r#"{{"callee": "{callee}", "selector": -1}}"#
+                ))),
+                None
+            );
+            assert_eq!(
+                invalid_index(bad_call(r#"{"callee": "0x01", "selector": 1}"#)),
+                Some(1)
+            );
+            assert_eq!(
+                invalid_index(bad_call(&format!(
+                    r#"{{"callee": "{}", "selector": 1}}"#,
+                    callee.replace('4', "g")
+                ))),
+                Some(1)
+            );
+            assert_eq!(profile.workflow_count(), 3);
+
+            // Runner overrides taking the commandline only need the generic action shape
+            let runner = WorkflowRunner {
+                contract: AccountId::from([1; 32]),
+                selector: ink::selector_bytes!("run"),
+                with_commandline: true,
+                output: RunnerOutput::Bool,
+            };
+            profile
+                .add_workflow_with_runner(
+                    name.clone(),
+                    r#"[{"cmd": "custom", "config": [1, 2]}]"#.into(),
+                    Some(runner.clone()),
+                )
+                .unwrap();
+            assert!(matches!(
+                profile.add_workflow_with_runner(name, r#"[{"cmd": ""}]"#.into(), Some(runner)),
+                Err(Error::InvalidCommandline { index: Some(0), .. })
+            ));
+        }
+
+        #[ink::test]
+        fn workflow_update_and_removal_works() {
+            let _ = env_logger::try_init();
+            pink_extension_runtime::mock_ext::mock_all_ext();
+
+            let mut profile = BrickProfile::default();
+            let name = String::from("TestWorkflow");
+            let wf1_id = profile.add_workflow(name.clone(), "[]".into()).unwrap();
+            let wf2_id = profile.add_workflow(name.clone(), "[]".into()).unwrap();
+            let wf3_id = profile.add_workflow(name.clone(), "[]".into()).unwrap();
+            let ea_id = profile
+                .generate_evm_account(String::from("https://testrpc.com"))
+                .unwrap();
+            profile.authorize_workflow(wf2_id, ea_id).unwrap();
+
+            // In-place update
+            let new_cmd = String::from("[{\"cmd\": \"log\"}]");
+            profile
+                .update_workflow(wf1_id, "Renamed".into(), new_cmd.clone())
+                .unwrap();
+            let wf1_details = profile.get_workflow(wf1_id).unwrap();
+            assert_eq!(wf1_details.name, "Renamed");
+            assert_eq!(wf1_details.commandline, new_cmd);
+
+            // Removal leaves a tombstone and drops the bindings
+            profile.remove_workflow(wf2_id).unwrap();
+            assert!(matches!(
+                profile.get_workflow(wf2_id),
+                Err(Error::WorkflowRemoved)
+            ));
+            assert!(profile.get_workflow_removed_at(wf2_id).is_some());
+            assert_eq!(profile.get_authorized_account(wf2_id), None);
+            assert!(matches!(
+                profile.update_workflow(wf2_id, name.clone(), "[]".into()),
+                Err(Error::WorkflowRemoved)
+            ));
+            assert_eq!(profile.workflow_count(), 3);
+            let wf4_id = profile.add_workflow(name.clone(), "[]".into()).unwrap();
+            assert_eq!(wf4_id, 3);
+
+            // Paging skips the removed workflows
+            let page: Vec<_> = profile
+                .get_workflows(0, 2)
+                .unwrap()
+                .into_iter()
+                .map(|w| w.id)
+                .collect();
+            assert_eq!(page, vec![wf1_id, wf3_id]);
+            let page: Vec<_> = profile
+                .get_workflows(wf3_id + 1, 2)
+                .unwrap()
+                .into_iter()
+                .map(|w| w.id)
+                .collect();
+            assert_eq!(page, vec![wf4_id]);
+
+            // Access control
+            let accounts = ink::env::test::default_accounts::<pink::PinkEnvironment>();
+            ink::env::test::set_caller::<pink::PinkEnvironment>(accounts.bob);
+            assert!(matches!(
+                profile.update_workflow(wf1_id, name, "[]".into()),
+                Err(Error::BadOrigin)
+            ));
+            assert!(matches!(
+                profile.remove_workflow(wf1_id),
+                Err(Error::BadOrigin)
+            ));
+        }
+
+        #[ink::test]
+        fn poll_report_works() {
+            let _ = env_logger::try_init();
+            pink_extension_runtime::mock_ext::mock_all_ext();
+
+            let mut profile = BrickProfile::default();
+            let wf_id = profile
+                .add_workflow(String::from("TestWorkflow"), String::from("[]"))
+                .unwrap();
+            assert_eq!(
+                profile.get_workflow_status(wf_id).unwrap(),
+                WorkflowStatus::default()
+            );
+
+            profile
+                .report_poll(wf_id, "poll-1".into(), true, None)
+                .unwrap();
+            let error = "x".repeat(300);
+            profile
+                .report_poll(wf_id, "poll-2".into(), false, Some(error))
+                .unwrap();
+            profile
+                .report_poll(wf_id, "poll-3".into(), false, Some("failed".into()))
+                .unwrap();
+            let status = profile.get_workflow_status(wf_id).unwrap();
+            assert_eq!(status.last_poll_id, Some("poll-3".into()));
+            assert!(status.last_success_at.is_some());
+            assert_eq!(status.consecutive_failures, 2);
+            assert_eq!(status.last_error, Some("failed".into()));
+            assert!(matches!(
+                profile.report_poll(wf_id, "<script>".into(), true, None),
+                Err(Error::InvalidPollId)
+            ));
+            assert_eq!(
+                truncate_poll_error("x".repeat(300)).len(),
+                MAX_POLL_ERROR_LEN
+            );
+
+            // Only owner and the poll reporter can report
+            let accounts = ink::env::test::default_accounts::<pink::PinkEnvironment>();
+            profile.set_poll_reporter(Some(accounts.bob)).unwrap();
+            ink::env::test::set_caller::<pink::PinkEnvironment>(accounts.bob);
+            profile
+                .report_poll(wf_id, "poll-4".into(), true, None)
+                .unwrap();
+            assert_eq!(
+                profile
+                    .get_workflow_status(wf_id)
+                    .unwrap()
+                    .consecutive_failures,
+                0
+            );
+            ink::env::test::set_caller::<pink::PinkEnvironment>(accounts.charlie);
+            assert!(matches!(
+                profile.report_poll(wf_id, "poll-5".into(), true, None),
+                Err(Error::BadOrigin)
+            ));
+        }
+
+        #[ink::test]
+        fn workflow_schedule_works() {
+            let _ = env_logger::try_init();
+            pink_extension_runtime::mock_ext::mock_all_ext();
+
+            let mut profile = BrickProfile::default();
+            let add_workflow = |profile: &mut BrickProfile, trigger: Option<Trigger>| {
+                let id = profile
+                    .add_workflow(String::from("TestWorkflow"), String::from("[]"))
+                    .unwrap();
+                let schedule = trigger.map(|trigger| Schedule {
+                    trigger,
+                    start_at: None,
+                    end_at: None,
+                });
+                profile.set_workflow_schedule(id, schedule).unwrap();
+                id
+            };
+            let interval_id = add_workflow(&mut profile, Some(Trigger::Interval(60_000)));
+            let cron_id = add_workflow(&mut profile, Some(Trigger::Cron("*/5 * * * *".into())));
+            let yearly_id = add_workflow(&mut profile, Some(Trigger::Cron("0 0 1 1 *".into())));
+            let disabled_id = add_workflow(&mut profile, Some(Trigger::Interval(60_000)));
+            profile.disable_workflow(disabled_id).unwrap();
+            let _ = add_workflow(&mut profile, None);
+            let future_id = add_workflow(&mut profile, None);
+            profile
+                .set_workflow_schedule(
+                    future_id,
+                    Some(Schedule {
+                        trigger: Trigger::Interval(60_000),
+                        start_at: Some(u64::MAX - 1),
+                        end_at: None,
+                    }),
+                )
+                .unwrap();
+            assert!(profile.get_workflow(cron_id).unwrap().schedule.is_some());
+
+            // 2023-11-14T22:15:00Z
+            let now = 1_700_000_100_000;
+            assert_eq!(profile.due_workflows(now), vec![interval_id, cron_id]);
+            assert!(!profile.due_workflows(now).contains(&yearly_id));
+
+            // Reported polls postpone the next run
+            ink::env::test::set_block_timestamp::<pink::PinkEnvironment>(now);
+            profile
+                .report_poll(interval_id, "poll-1".into(), true, None)
+                .unwrap();
+            profile
+                .report_poll(cron_id, "poll-1".into(), true, None)
+                .unwrap();
+            assert!(profile.due_workflows(now + 30_000).is_empty());
+            assert_eq!(profile.due_workflows(now + 60_000), vec![interval_id]);
+            assert_eq!(
+                profile.due_workflows(now + 5 * 60_000),
+                vec![interval_id, cron_id]
+            );
+
+            // Validation
+            for trigger in [
+                Trigger::Interval(0),
+                Trigger::Cron("61 * * * *".into()),
+                Trigger::Cron("* * *".into()),
+                Trigger::Cron("*/0 * * * *".into()),
+                Trigger::Cron("*/60 * * * *".into()),
+                Trigger::Cron("*/4294967296 * * * *".into()),
+            ] {
+                let schedule = Schedule {
+                    trigger,
+                    start_at: None,
+                    end_at: None,
+                };
+                assert!(matches!(
+                    profile.set_workflow_schedule(interval_id, Some(schedule)),
+                    Err(Error::InvalidSchedule(_))
+                ));
+            }
+            let schedule = Schedule {
+                trigger: Trigger::Interval(1),
+                start_at: Some(2),
+                end_at: Some(1),
+            };
+            assert!(matches!(
+                profile.set_workflow_schedule(interval_id, Some(schedule)),
+                Err(Error::InvalidSchedule(_))
+            ));
+
+            // Only the workflows with schedules are scanned, with the cron expressions parsed
+            assert!(profile.workflow_crons.get(cron_id).is_some());
+            profile.set_workflow_schedule(cron_id, None).unwrap();
+            assert!(profile.workflow_crons.get(cron_id).is_none());
+            profile.remove_workflow(yearly_id).unwrap();
+            assert_eq!(
+                profile.scheduled_workflows.get().unwrap(),
+                vec![interval_id, disabled_id, future_id]
+            );
+            let schedule = Schedule {
+                trigger: Trigger::Interval(60_000),
+                start_at: None,
+                end_at: None,
+            };
+            profile
+                .set_workflow_schedule(interval_id, Some(schedule))
+                .unwrap();
+            assert_eq!(profile.scheduled_workflows.get().unwrap().len(), 3);
+
+            // Day of week, 2023-11-14 is a Tuesday
+            let cron = crate::cron::CronExpr::parse("15 22 * * 2").unwrap();
+            assert!(cron.matches(now));
+            let cron = crate::cron::CronExpr::parse("15 22 * * 3").unwrap();
+            assert!(!cron.matches(now));
+            let cron = crate::cron::CronExpr::parse("15 22 14 11 3").unwrap();
+            assert!(cron.matches(now));
+        }
+
+        #[ink::test]
+        fn workflow_roles_works() {
+            let _ = env_logger::try_init();
+            pink_extension_runtime::mock_ext::mock_all_ext();
+
+            let mut profile = BrickProfile::default();
+            let rpc = String::from("https://testrpc.com");
+            let wf_id = profile
+                .add_workflow(String::from("TestWorkflow"), String::from("[]"))
+                .unwrap();
+            let ea1_id = profile.generate_evm_account(rpc.clone()).unwrap();
+            let ea2_id = profile.generate_evm_account(rpc.clone()).unwrap();
+            let gas_payer = String::from("gas_payer");
+
+            profile.authorize_workflow(wf_id, ea1_id).unwrap();
+            profile
+                .authorize_workflow_role(wf_id, gas_payer.clone(), ea2_id)
+                .unwrap();
+            assert!(matches!(
+                profile.authorize_workflow_role(wf_id, String::from("bad role"), ea2_id),
+                Err(Error::InvalidRole)
+            ));
+            assert_eq!(
+                profile.get_authorized_account_by_role(wf_id, DEFAULT_ROLE.into()),
+                Some(ea1_id)
+            );
+            let wf_details = profile.get_workflow(wf_id).unwrap();
+            assert_eq!(wf_details.authorized_account, Some(ea1_id));
+            assert_eq!(wf_details.role_accounts, vec![(gas_payer.clone(), ea2_id)]);
+
+            // Role-aware session reads
+            let ea1_address = profile.get_evm_account_address(ea1_id).unwrap();
+            let ea2_address = profile.get_evm_account_address(ea2_id).unwrap();
+            let contract = ink::env::account_id::<pink::PinkEnvironment>();
+            ink::env::test::set_callee::<pink::PinkEnvironment>(contract);
+            ink::env::test::set_caller::<pink::PinkEnvironment>(contract);
+            profile.workflow_session.set(&wf_id);
+            assert_eq!(
+                profile.get_current_evm_account_address().unwrap(),
+                ea1_address
+            );
+            assert_eq!(
+                profile
+                    .get_current_evm_account_address_by_role(gas_payer.clone())
+                    .unwrap(),
+                ea2_address
+            );
+            assert!(matches!(
+                profile.get_current_rpc_by_role(String::from("treasury")),
+                Err(Error::NoAuthorizedExternalAccount)
+            ));
+
+            // Revocation
+            ink::env::test::set_caller::<pink::PinkEnvironment>(profile.owner());
+            profile
+                .revoke_workflow_role(wf_id, gas_payer.clone())
+                .unwrap();
+            assert_eq!(
+                profile.get_authorized_account_by_role(wf_id, gas_payer.clone()),
+                None
+            );
+            assert!(profile
+                .get_workflow(wf_id)
+                .unwrap()
+                .role_accounts
+                .is_empty());
+            // Nothing left to revoke
+            assert!(matches!(
+                profile.revoke_workflow_role(wf_id, gas_payer.clone()),
+                Err(Error::RoleNotBound)
+            ));
+            assert!(matches!(
+                profile.revoke_workflow_role(wf_id, String::from("bad role")),
+                Err(Error::InvalidRole)
+            ));
+        }
+
+        #[ink::test]
+        fn chain_id_works() {
+            let (mut profile, wf_id, ea_id) = setup();
+
+            // Cached chain id
+            assert_eq!(profile.get_chain_id(ea_id).unwrap(), U256::from(1));
+
+            // Transactions for other chains are rejected
+            let contract = ink::env::account_id::<pink::PinkEnvironment>();
+            ink::env::test::set_callee::<pink::PinkEnvironment>(contract);
+            ink::env::test::set_caller::<pink::PinkEnvironment>(contract);
+            profile.workflow_session.set(&wf_id);
+            let tx = br#"{"to":"0xf8ce0975502a96e897505fd626234a9a0126c072","chainId":"0x89"}"#;
+            assert!(matches!(
+                profile.sign_evm_transaction(tx.to_vec()),
+                Err(Error::ChainIdMismatch)
+            ));
+
+            // Changing the rpc clears the cache
+            ink::env::test::set_caller::<pink::PinkEnvironment>(profile.owner());
+            profile
+                .set_rpc_endpoint(ea_id, String::from("https://testrpc.com"))
+                .unwrap();
+            let accounts = profile.get_all_evm_accounts().unwrap();
+            assert_eq!(accounts[0].chain_id, None);
+
+            // The chain id read from the rpcs is cached locally until they change
+            let mut account = profile.ensure_evm_account(ea_id).unwrap();
+            save_chain_id(&account, 137);
+            assert_eq!(profile.get_chain_id(ea_id).unwrap(), U256::from(137));
+            account.rpcs = vec![String::from("https://other.testrpc.com")];
+            assert_eq!(load_chain_id(&account), None);
+        }
+
+        #[ink::test]
+        fn signing_policy_works() {
+            let (mut profile, wf_id, ea_id) = setup();
+
+            let policy = SigningPolicy {
+                allowed_to: Some(vec![[0xf8; 20]]),
+                max_value: Some(1000),
+                daily_spend_limit: Some(1500),
+                ..Default::default()
+            };
+            profile
+                .set_signing_policy(wf_id, ea_id, policy.clone())
+                .unwrap();
+            assert_eq!(profile.get_signing_policy(wf_id, ea_id), Some(policy));
+
+            let contract = ink::env::account_id::<pink::PinkEnvironment>();
+            ink::env::test::set_callee::<pink::PinkEnvironment>(contract);
+            ink::env::test::set_caller::<pink::PinkEnvironment>(contract);
+            profile.workflow_session.set(&wf_id);
+            let tx = |to: &str, value: &str| {
+                format!(r#"{{"to":"0x{to}","value":"{value}","nonce":"0x0","gas":"0x0","gasPrice":"0x0"}}"#)
+                    .into_bytes()
+            };
+            let allowed = "f8".repeat(20);
+
+            assert!(matches!(
+                profile.sign_evm_transaction(tx(&"01".repeat(20), "0x1")),
+                Err(Error::PolicyViolation(_))
+            ));
+            assert!(matches!(
+                profile.sign_evm_transaction(tx(&allowed, "0x7d0")),
+                Err(Error::PolicyViolation(_))
+            ));
+            // 1000 + 1000 exceeds the daily limit
+            profile.sign_evm_transaction(tx(&allowed, "0x3e8")).unwrap();
+            assert!(matches!(
+                profile.sign_evm_transaction(tx(&allowed, "0x3e8")),
+                Err(Error::PolicyViolation(_))
+            ));
+        }
+
+        #[ink::test]
+        fn substrate_account_works() {
+            let _ = env_logger::try_init();
+            pink_extension_runtime::mock_ext::mock_all_ext();
+
+            let rpc = String::from("https://testrpc.com");
+            let mut profile = BrickProfile::default();
+            assert!(matches!(
+                profile.generate_substrate_account(rpc.clone(), SubstrateScheme::Sr25519, 16384),
+                Err(Error::InvalidSs58Prefix)
+            ));
+            let evm_id = profile.generate_evm_account(rpc.clone()).unwrap();
+            let sr_id = profile
+                .generate_substrate_account(rpc.clone(), SubstrateScheme::Sr25519, 42)
+                .unwrap();
+            let ed_id = profile
+                .generate_substrate_account(rpc.clone(), SubstrateScheme::Ed25519, 0)
+                .unwrap();
+            assert_eq!(profile.external_account_count(), 3);
+
+            assert!(profile
+                .get_substrate_account_address(sr_id)
+                .unwrap()
+                .starts_with('5'));
+            assert!(profile
+                .get_substrate_account_address(ed_id)
+                .unwrap()
+                .starts_with('1'));
+            assert!(matches!(
+                profile.get_substrate_account_address(evm_id),
+                Err(Error::NotSubstrateAccount)
+            ));
+            assert!(matches!(
+                profile.get_evm_account_address(sr_id),
+                Err(Error::NotEvmAccount)
+            ));
+            assert_eq!(profile.get_all_evm_accounts().unwrap().len(), 1);
+            assert_eq!(profile.get_all_substrate_accounts().unwrap().len(), 2);
+
+            let wf_id = profile
+                .add_workflow(String::from("TestWorkflow"), String::from("[]"))
+                .unwrap();
+            profile.authorize_workflow(wf_id, sr_id).unwrap();
+            profile
+                .authorize_workflow_role(wf_id, String::from("fee_payer"), ed_id)
+                .unwrap();
+
+            // Signing is only allowed in the workflow session
+            assert!(matches!(
+                profile.sign_substrate_extrinsic(vec![0; 8]),
+                Err(Error::BadWorkflowSession)
+            ));
+            profile.workflow_session.set(&wf_id);
+            assert!(matches!(
+                profile.sign_evm_transaction(vec![]),
+                Err(Error::NotEvmAccount)
+            ));
+
+            let account = profile.get_current_substrate_account().unwrap();
+            let payload = vec![1; 8];
+            let signature = profile.sign_substrate_extrinsic(payload.clone()).unwrap();
+            assert!(signing::verify(
+                &payload,
+                &account.public_key,
+                &signature,
+                SigType::Sr25519
+            ));
+
+            // Long payloads are signed by hash
+            let account = profile
+                .get_current_substrate_account_by_role(String::from("fee_payer"))
+                .unwrap();
+            let payload = vec![1; 300];
+            let signature = profile
+                .sign_substrate_extrinsic_by_role(String::from("fee_payer"), payload.clone())
+                .unwrap();
+            let mut hash = <Blake2x256 as HashOutput>::Type::default();
+            ink::env::hash_bytes::<Blake2x256>(&payload, &mut hash);
+            assert!(signing::verify(
+                &hash,
+                &account.public_key,
+                &signature,
+                SigType::Ed25519
+            ));
+        }
+
+        #[ink::test]
+        fn nonce_reservation_works() {
+            let (mut profile, wf_id, ea_id) = setup();
+            assert_eq!(load_reserved_nonce(ea_id, 0), None);
+
+            let contract = ink::env::account_id::<pink::PinkEnvironment>();
+            ink::env::test::set_callee::<pink::PinkEnvironment>(contract);
+            ink::env::test::set_caller::<pink::PinkEnvironment>(contract);
+            profile.workflow_session.set(&wf_id);
+            let tx = |nonce: &str| {
+                format!(
+                    r#"{{"to":"0x{}","nonce":"{nonce}","gas":"0x0","gasPrice":"0x0"}}"#,
+                    "f8".repeat(20)
+                )
+                .into_bytes()
+            };
+
+            // Signing a given nonce without a reservation reserves nothing
+            profile.sign_evm_transaction(tx("0x5")).unwrap();
+            assert_eq!(load_reserved_nonce(ea_id, 0), None);
+
+            // Only signing the reserved nonce advances the reservation
+            save_reserved_nonce(ea_id, U256::from(6), 0);
+            profile.sign_evm_transaction(tx("0x5")).unwrap();
+            assert_eq!(load_reserved_nonce(ea_id, 0), Some(U256::from(6)));
+            profile.sign_evm_transaction(tx("0x6")).unwrap();
+            assert_eq!(load_reserved_nonce(ea_id, 0), Some(U256::from(7)));
+            profile.sign_evm_transaction(tx("0x9")).unwrap();
+            assert_eq!(load_reserved_nonce(ea_id, 0), Some(U256::from(7)));
+
+            // The reservations expire
+            assert_eq!(load_reserved_nonce(ea_id, NONCE_RESERVATION_TTL_MS), None);
+
+            save_reserved_nonce(ea_id, U256::MAX, 0);
+            assert!(matches!(
+                profile.sign_evm_transaction(tx(&format!("{:#x}", U256::MAX))),
+                Err(Error::NonceOverflow)
+            ));
+
+            assert!(matches!(
+                profile.get_pending_nonce(ea_id),
+                Err(Error::BadOrigin)
+            ));
+        }
+
+        #[ink::test]
+        fn signed_transaction_log_works() {
+            let (mut profile, wf_id, ea_id) = setup();
+            assert!(profile
+                .get_signed_transactions(ea_id, 0, 10)
+                .unwrap()
+                .is_empty());
+
+            let contract = ink::env::account_id::<pink::PinkEnvironment>();
+            ink::env::test::set_callee::<pink::PinkEnvironment>(contract);
+            ink::env::test::set_caller::<pink::PinkEnvironment>(contract);
+            profile.workflow_session.set(&wf_id);
+            let to = H160::from([0xf8; 20]);
+            let tx = |nonce: u64| {
+                format!(
+                    r#"{{"to":"{to:?}","nonce":"{nonce:#x}","value":"0x64","gas":"0x5208","gasPrice":"0x0"}}"#
+                )
+                .into_bytes()
+            };
+            for nonce in 0..(MAX_SIGNED_TX_RECORDS as u64 + 2) {
+                profile.sign_evm_transaction(tx(nonce)).unwrap();
+            }
+            assert!(matches!(
+                profile.get_signed_transactions(ea_id, 0, 2),
+                Err(Error::BadOrigin)
+            ));
+            let accounts = ink::env::test::default_accounts::<pink::PinkEnvironment>();
+            ink::env::test::set_caller::<pink::PinkEnvironment>(accounts.alice);
+
+            // The newest first, and the oldest ones are dropped
+            let records = profile.get_signed_transactions(ea_id, 0, 2).unwrap();
+            assert_eq!(records.len(), 2);
+            assert_eq!(records[0].nonce, U256::from(MAX_SIGNED_TX_RECORDS + 1));
+            assert_eq!(records[1].nonce, U256::from(MAX_SIGNED_TX_RECORDS));
+            assert_eq!(records[0].workflow_id, wf_id);
+            assert_eq!(records[0].to, Some(to));
+            assert_eq!(records[0].value, U256::from(100));
+            assert_eq!(records[0].gas_limit, U256::from(21000));
+            let records = profile.get_signed_transactions(ea_id, 0, 100).unwrap();
+            assert_eq!(records.len(), MAX_SIGNED_TX_RECORDS);
+            assert_eq!(records.last().unwrap().nonce, U256::from(2));
+
+            assert!(matches!(
+                profile.get_signed_transactions(100, 0, 10),
+                Err(Error::ExternalAccountNotFound)
+            ));
+        }
+
+        #[ink::test]
+        fn low_watermark_works() {
+            let _ = env_logger::try_init();
+            pink_extension_runtime::mock_ext::mock_all_ext();
+
+            let mut profile = BrickProfile::default();
+            // Unreachable, the balances are unknown
+            let rpc = String::from("http://127.0.0.1:1");
+            let ea_id = profile.generate_evm_account(rpc.clone()).unwrap();
+            let sub_id = profile
+                .generate_substrate_account(rpc, SubstrateScheme::Sr25519, 42)
+                .unwrap();
+
+            assert_eq!(profile.get_low_watermark(ea_id).unwrap(), None);
+            profile.set_low_watermark(ea_id, Some(1000)).unwrap();
+            assert_eq!(profile.get_low_watermark(ea_id).unwrap(), Some(1000));
+            assert!(matches!(
+                profile.set_low_watermark(sub_id, Some(1000)),
+                Err(Error::NotEvmAccount)
+            ));
+
+            // Only the EVM accounts are listed, and unknown balances are not reported as low
+            let balances = profile.get_account_balances().unwrap();
+            assert_eq!(balances.len(), 1);
+            assert_eq!(balances[0].id, ea_id);
+            assert_eq!(balances[0].balance, None);
+            assert_eq!(balances[0].low_watermark, Some(1000));
+            assert!(profile.get_low_funds_accounts().unwrap().is_empty());
+
+            let balance = |balance: Option<u64>, low_watermark: Option<u128>| AccountBalance {
+                id: ea_id,
+                address: H160::zero(),
+                balance: balance.map(U256::from),
+                low_watermark,
+            };
+            let low_watermark = profile.get_low_watermark(ea_id).unwrap();
+            assert!(balance(Some(999), low_watermark).is_low());
+            assert!(!balance(Some(1000), low_watermark).is_low());
+            assert!(!balance(None, low_watermark).is_low());
+
+            profile.set_low_watermark(ea_id, None).unwrap();
+            assert_eq!(profile.get_low_watermark(ea_id).unwrap(), None);
+            assert_eq!(
+                profile.get_account_balances().unwrap()[0].low_watermark,
+                None
+            );
+            assert!(!balance(Some(999), None).is_low());
+
+            // Access control
+            let accounts = ink::env::test::default_accounts::<pink::PinkEnvironment>();
+            ink::env::test::set_caller::<pink::PinkEnvironment>(accounts.bob);
+            assert!(matches!(
+                profile.set_low_watermark(ea_id, Some(1000)),
+                Err(Error::BadOrigin)
+            ));
+            assert!(matches!(
+                profile.get_low_watermark(ea_id),
+                Err(Error::BadOrigin)
+            ));
+            assert!(matches!(
+                profile.get_account_balances(),
+                Err(Error::BadOrigin)
+            ));
+            assert!(matches!(
+                profile.get_low_funds_accounts(),
+                Err(Error::BadOrigin)
+            ));
+        }
+
+        #[ink::test]
+        fn rpc_failover_works() {
+            let _ = env_logger::try_init();
+            pink_extension_runtime::mock_ext::mock_all_ext();
+
+            let rpcs: Vec<String> = ["https://a.com", "https://b.com", "https://c.com"]
+                .into_iter()
+                .map(String::from)
+                .collect();
+            let mut profile = BrickProfile::default();
+            let ea_id = profile.generate_evm_account(rpcs[0].clone()).unwrap();
+            assert!(matches!(
+                profile.set_rpc_endpoints(ea_id, vec![]),
+                Err(Error::NoRpcEndpoint)
+            ));
+            profile.set_rpc_endpoints(ea_id, rpcs.clone()).unwrap();
+            assert_eq!(profile.get_rpc_endpoints(ea_id).unwrap(), rpcs);
+            assert_eq!(profile.get_rpc_endpoint(ea_id).unwrap(), rpcs[0]);
+
+            // Failed endpoints are tried last until they cool down
+            let account = profile.ensure_evm_account(ea_id).unwrap();
+            let mut results = vec![Err(pink_web3::Error::Unreachable), Ok(1)].into_iter();
+            assert_eq!(
+                profile.call_rpc(&account, |_| results.next().unwrap()),
+                Ok(1)
+            );
+            assert_eq!(profile.get_rpc_endpoint(ea_id).unwrap(), rpcs[1]);
+            assert_eq!(ordered_rpcs(&rpcs, 0), vec![&rpcs[1], &rpcs[2], &rpcs[0]]);
+            ink::env::test::set_block_timestamp::<pink::PinkEnvironment>(RPC_COOLDOWN_MS);
+            assert_eq!(profile.get_rpc_endpoint(ea_id).unwrap(), rpcs[0]);
+
+            // Two endpoints must agree in quorum mode
+            profile.set_rpc_quorum(ea_id, true).unwrap();
+            let account = profile.ensure_evm_account(ea_id).unwrap();
+            let mut results = vec![Ok(1), Ok(2), Ok(2)].into_iter();
+            assert_eq!(
+                profile.call_rpc_with_quorum(&account, |_| results.next().unwrap()),
+                Ok(2)
+            );
+            let mut results = vec![Ok(1), Err(pink_web3::Error::Unreachable), Ok(3)].into_iter();
+            assert!(profile
+                .call_rpc_with_quorum(&account, |_| results.next().unwrap())
+                .is_err());
+
+            // Access control
+            let accounts = ink::env::test::default_accounts::<pink::PinkEnvironment>();
+            ink::env::test::set_caller::<pink::PinkEnvironment>(accounts.bob);
+            assert!(matches!(
+                profile.set_rpc_endpoints(ea_id, rpcs.clone()),
+                Err(Error::BadOrigin)
+            ));
+            assert!(matches!(
+                profile.set_rpc_quorum(ea_id, false),
+                Err(Error::BadOrigin)
+            ));
+        }
+
+        #[ink::test]
+        fn operator_roles_works() {
+            let _ = env_logger::try_init();
+            pink_extension_runtime::mock_ext::mock_all_ext();
+
+            let accounts = ink::env::test::default_accounts::<pink::PinkEnvironment>();
+            let mut profile = BrickProfile::default();
+            let wf_id = profile
+                .add_workflow(String::from("TestWorkflow"), String::from("[]"))
+                .unwrap();
+            let ea_id = profile
+                .generate_evm_account(String::from("https://testrpc.com"))
+                .unwrap();
+
+            let events = ink::env::test::recorded_events().count();
+            profile
+                .grant_operator_role(accounts.bob, OperatorRole::AccountOperator)
+                .unwrap();
+            profile
+                .grant_operator_role(accounts.bob, OperatorRole::AccountOperator)
+                .unwrap();
+            profile
+                .grant_operator_role(accounts.charlie, OperatorRole::Viewer)
+                .unwrap();
+            assert_eq!(ink::env::test::recorded_events().count(), events + 2);
+            assert_eq!(
+                profile.get_operators(),
+                vec![
+                    (accounts.bob, vec![OperatorRole::AccountOperator]),
+                    (accounts.charlie, vec![OperatorRole::Viewer]),
+                ]
+            );
+            assert!(profile.has_permission(accounts.bob, Permission::PauseWorkflows));
+            assert!(!profile.has_permission(accounts.bob, Permission::EditWorkflows));
+            assert!(!profile.has_permission(accounts.charlie, Permission::PauseWorkflows));
+
+            // Account operators can pause workflows but not edit them
+            ink::env::test::set_caller::<pink::PinkEnvironment>(accounts.bob);
+            profile.disable_workflow(wf_id).unwrap();
+            profile
+                .set_rpc_endpoint(ea_id, String::from("https://b.com"))
+                .unwrap();
+            assert!(matches!(
+                profile.update_workflow(wf_id, String::from("Renamed"), String::from("[]")),
+                Err(Error::BadOrigin)
+            ));
+            assert!(matches!(
+                profile.dump_evm_account(ea_id),
+                Err(Error::BadOrigin)
+            ));
+            assert!(matches!(
+                profile.grant_operator_role(accounts.bob, OperatorRole::WorkflowEditor),
+                Err(Error::BadOrigin)
+            ));
+
+            // Viewers can only read
+            ink::env::test::set_caller::<pink::PinkEnvironment>(accounts.charlie);
+            assert_eq!(
+                profile.get_rpc_endpoint(ea_id).unwrap(),
+                String::from("https://b.com")
+            );
+            assert!(matches!(
+                profile.enable_workflow(wf_id),
+                Err(Error::BadOrigin)
+            ));
+
+            ink::env::test::set_caller::<pink::PinkEnvironment>(accounts.alice);
+            let events = ink::env::test::recorded_events().count();
+            profile
+                .revoke_operator_role(accounts.bob, OperatorRole::AccountOperator)
+                .unwrap();
+            assert_eq!(ink::env::test::recorded_events().count(), events + 1);
+            assert_eq!(profile.get_operator_roles(accounts.bob), vec![]);
+            assert_eq!(profile.get_operators().len(), 1);
+            ink::env::test::set_caller::<pink::PinkEnvironment>(accounts.bob);
+            assert!(matches!(
+                profile.enable_workflow(wf_id),
+                Err(Error::BadOrigin)
+            ));
+        }
+
+        #[ink::test]
+        fn ownership_transfer_works() {
+            let _ = env_logger::try_init();
+            pink_extension_runtime::mock_ext::mock_all_ext();
 
-            Ok(signed_tx.raw_transaction.0)
-        }
+            let accounts = ink::env::test::default_accounts::<pink::PinkEnvironment>();
+            let mut profile = BrickProfile::default();
+            assert_eq!(profile.owner(), accounts.alice);
+            assert!(!profile.ownership_renounced());
 
-        /// Returns BadOrigin error if the caller is not the owner.
-        fn ensure_owner(&self) -> Result<()> {
-            if self.env().caller() == self.owner {
-                Ok(())
-            } else {
+            profile.propose_owner(Some(accounts.bob)).unwrap();
+            assert_eq!(profile.pending_owner(), Some(accounts.bob));
+            assert_eq!(profile.owner(), accounts.alice);
+
+            // Only the proposed owner can accept
+            ink::env::test::set_caller::<pink::PinkEnvironment>(accounts.charlie);
+            assert_eq!(
+                profile.accept_ownership(),
+                Err(ownership::Error::NotPendingOwner)
+            );
+            ink::env::test::set_caller::<pink::PinkEnvironment>(accounts.bob);
+            profile.accept_ownership().unwrap();
+            assert_eq!(profile.owner(), accounts.bob);
+            assert_eq!(profile.pending_owner(), None);
+            assert_eq!(ink::env::test::recorded_events().count(), 1);
+            profile
+                .add_workflow(String::from("TestWorkflow"), String::from("[]"))
+                .unwrap();
+
+            // The previous owner loses the access
+            ink::env::test::set_caller::<pink::PinkEnvironment>(accounts.alice);
+            assert_eq!(
+                profile.propose_owner(Some(accounts.alice)),
+                Err(ownership::Error::NotOwner)
+            );
+
+            ink::env::test::set_caller::<pink::PinkEnvironment>(accounts.bob);
+            let events = ink::env::test::recorded_events().count();
+            profile.renounce_ownership().unwrap();
+            assert!(profile.ownership_renounced());
+            assert_eq!(profile.owner(), accounts.bob);
+            assert_eq!(ink::env::test::recorded_events().count(), events + 1);
+            assert!(matches!(
+                profile.add_workflow(String::from("TestWorkflow"), String::from("[]")),
                 Err(Error::BadOrigin)
-            }
+            ));
         }
 
-        fn ensure_workflow_session(&self) -> Result<WorkflowId> {
-            match self.workflow_session.get() {
-                Some(id) => Ok(id),
-                None => Err(Error::BadWorkflowSession),
-            }
-        }
+        #[ink::test]
+        fn events_works() {
+            let _ = env_logger::try_init();
+            pink_extension_runtime::mock_ext::mock_all_ext();
 
-        fn ensure_workflow(&self, id: WorkflowId) -> Result<Workflow> {
-            self.workflows.get(id).ok_or(Error::WorkflowNotFound)
-        }
+            let mut profile = BrickProfile::default();
+            let wf_id = profile
+                .add_workflow(String::from("TestWorkflow"), String::from("[]"))
+                .unwrap();
+            profile.disable_workflow(wf_id).unwrap();
+            // Nothing changes
+            profile.disable_workflow(wf_id).unwrap();
+            let ea_id = profile
+                .generate_evm_account(String::from("https://testrpc.com"))
+                .unwrap();
+            profile
+                .set_rpc_endpoint(ea_id, String::from("https://b.com"))
+                .unwrap();
+            profile
+                .authorize_workflow_role(wf_id, String::from("gas_payer"), ea_id)
+                .unwrap();
+            profile
+                .revoke_workflow_role(wf_id, String::from("gas_payer"))
+                .unwrap();
+            profile.dump_evm_account(ea_id).unwrap();
 
-        fn ensure_enabled_workflow(&self, id: WorkflowId) -> Result<Workflow> {
-            let workflow = self.ensure_workflow(id)?;
-            if !workflow.enabled {
-                Err(Error::WorkflowDisabled)
-            } else {
-                Ok(workflow)
-            }
+            type Event = <BrickProfile as ink::reflect::ContractEventBase>::Type;
+            let events: Vec<_> = ink::env::test::recorded_events().collect();
+            let decoded: Vec<Event> = events
+                .iter()
+                .map(|event| Event::decode(&mut &event.data[..]).unwrap())
+                .collect();
+            assert_eq!(decoded.len(), 7);
+            assert!(matches!(
+                &decoded[0],
+                Event::WorkflowAdded(WorkflowAdded { workflow_id: 0, name }) if name == "TestWorkflow"
+            ));
+            assert!(matches!(
+                decoded[1],
+                Event::WorkflowDisabled(WorkflowDisabled { workflow_id: 0 })
+            ));
+            assert!(matches!(
+                decoded[2],
+                Event::ExternalAccountCreated(ExternalAccountCreated {
+                    account_id: 0,
+                    account_type: ExternalAccountType::Generated,
+                    key_type: KeyType::Evm,
+                })
+            ));
+            assert!(matches!(
+                decoded[3],
+                Event::RpcEndpointsChanged(RpcEndpointsChanged { account_id: 0, .. })
+            ));
+            assert!(matches!(
+                &decoded[4],
+                Event::WorkflowAuthorizationChanged(WorkflowAuthorizationChanged {
+                    workflow_id: 0,
+                    account_id: Some(0),
+                    role,
+                }) if role == "gas_payer"
+            ));
+            assert!(matches!(
+                decoded[5],
+                Event::WorkflowAuthorizationChanged(WorkflowAuthorizationChanged {
+                    account_id: None,
+                    ..
+                })
+            ));
+            assert!(matches!(
+                decoded[6],
+                Event::ExternalAccountDisabled(ExternalAccountDisabled {
+                    account_id: 0,
+                    account_type: ExternalAccountType::Dumped,
+                })
+            ));
+            // Keyed by the event signature, the workflow id and the account id
+            assert_eq!(events[4].topics.len(), 3);
         }
 
-        fn ensure_external_account(&self, id: ExternalAccountId) -> Result<ExternalAccount> {
-            self.external_accounts
-                .get(id)
-                .ok_or(Error::ExternalAccountNotFound)
-        }
+        #[ink::test]
+        fn key_export_works() {
+            let _ = env_logger::try_init();
+            pink_extension_runtime::mock_ext::mock_all_ext();
 
-        fn ensure_enabled_external_account(
-            &self,
-            id: ExternalAccountId,
-        ) -> Result<ExternalAccount> {
-            let account = self.ensure_external_account(id)?;
-            if !account.enabled {
+            let rpc = String::from("https://testrpc.com");
+            let mut profile = BrickProfile::default();
+            let ea_id = profile.generate_evm_account(rpc.clone()).unwrap();
+            let address = profile.get_evm_account_address(ea_id).unwrap();
+            let recipient = profile.get_import_public_key().unwrap();
+            let this = ink::env::account_id::<pink::PinkEnvironment>();
+
+            assert!(matches!(
+                profile.get_exported_key(ea_id, recipient.clone()),
+                Err(Error::OnlyExportedAccount)
+            ));
+            profile.export_evm_account(ea_id, this).unwrap();
+            assert!(matches!(
+                profile.get_evm_account_address(ea_id),
                 Err(Error::ExternalAccountDisabled)
-            } else {
-                Ok(account)
-            }
-        }
+            ));
+            assert!(matches!(
+                profile.get_exported_key(ea_id, vec![0; 33]),
+                Err(Error::BadPublicKey)
+            ));
+            let exported = profile.get_exported_key(ea_id, recipient).unwrap();
 
-        fn ensure_dumped_external_account(&self, id: ExternalAccountId) -> Result<ExternalAccount> {
-            let account = self.ensure_external_account(id)?;
-            if account.account_type != ExternalAccountType::Dumped {
-                Err(Error::OnlyDumpedAccount)
-            } else {
-                Ok(account)
-            }
-        }
-    }
+            // Tampered keys are rejected
+            let mut tampered = exported.clone();
+            tampered.from_account += 1;
+            assert!(matches!(
+                profile.import_exported_evm_account(rpc.clone(), tampered),
+                Err(Error::BadExportedKey)
+            ));
+            let mut tampered = exported.clone();
+            tampered.ciphertext[40] ^= 1;
+            assert!(matches!(
+                profile.import_exported_evm_account(rpc.clone(), tampered),
+                Err(Error::BadExportedKey)
+            ));
 
-    #[cfg(test)]
-    mod tests {
-        use super::*;
-        use alloc::collections::BTreeMap;
-        use pink::warn;
+            let new_id = profile
+                .import_exported_evm_account(rpc.clone(), exported.clone())
+                .unwrap();
+            assert_eq!(profile.get_evm_account_address(new_id).unwrap(), address);
 
-        struct EnvVars {
-            rpc: String,
-            key: Vec<u8>,
+            // An exported key can only be imported once
+            assert!(matches!(
+                profile.import_exported_evm_account(rpc, exported),
+                Err(Error::AccountAlreadyImported)
+            ));
         }
 
-        fn get_env(key: &str) -> String {
-            std::env::var(key).expect("env not found")
-        }
-        fn config() -> EnvVars {
-            dotenvy::dotenv().ok();
-            let rpc = get_env("RPC");
-            let key = hex::decode(get_env("PRIVKEY")).expect("hex decode failed");
-            EnvVars { rpc, key }
+        #[ink::test]
+        fn poll_many_works() {
+            let _ = env_logger::try_init();
+            pink_extension_runtime::mock_ext::mock_all_ext();
+
+            let mut profile = BrickProfile::default();
+            let cmd = String::from("[{\"cmd\": \"log\"}]");
+            let enabled_id = profile.add_workflow("Enabled".into(), cmd.clone()).unwrap();
+            let disabled_id = profile.add_workflow("Disabled".into(), cmd).unwrap();
+            profile.disable_workflow(disabled_id).unwrap();
+
+            assert!(matches!(
+                profile.poll_many(vec![enabled_id], "<script>".into()),
+                Err(Error::InvalidPollId)
+            ));
+            assert!(profile
+                .poll_many(vec![], "poll-1".into())
+                .unwrap()
+                .is_empty());
+
+            // Failures are reported per workflow
+            let results = profile
+                .poll_many(vec![enabled_id, disabled_id, 100], "poll-1".into())
+                .unwrap();
+            assert_eq!(results.len(), 3);
+            assert!(matches!(results[0], Err(Error::NotConfigured)));
+            assert!(matches!(results[1], Err(Error::WorkflowDisabled)));
+            assert!(matches!(results[2], Err(Error::WorkflowNotFound)));
         }
 
         #[ink::test]
-        fn workflow_management_works() {
+        fn storage_migration_works() {
             let _ = env_logger::try_init();
             pink_extension_runtime::mock_ext::mock_all_ext();
 
             let mut profile = BrickProfile::default();
+            assert_eq!(profile.storage_version(), STORAGE_VERSION);
+            let events = ink::env::test::recorded_events().count();
+            profile.migrate().unwrap();
+            assert_eq!(ink::env::test::recorded_events().count(), events);
 
-            // Basic add and get
-            let cmd = String::from("[
-                {\"cmd\": \"fetch\", \"config\": {\"returnTextBody\":true,\"url\":\"https://min-api.cryptocompare.com/data/price?fsym=ETH&tsyms=BTC,USD,EUR\"}},
-                {\"cmd\": \"eval\", \"config\": \"Math.round(JSON.parse(input.body).USD)\"},
-                {\"cmd\": \"eval\", \"config\": \"numToUint8Array32(input)\"},
-            ]");
-            let name = String::from("TestWorkflow");
-            let wf1_id = profile.add_workflow(name.clone(), cmd.clone()).unwrap();
-            let _ = profile.add_workflow(name.clone(), cmd.clone()).unwrap();
-            assert_eq!(profile.workflow_count(), 2);
+            // The root storage keeps the encoding of the first release
+            let mut root = Vec::new();
+            ink::storage::traits::Storable::encode(&profile, &mut root);
+            let legacy_root = (
+                profile.owner(),
+                &profile.config,
+                profile.next_workflow_id,
+                profile.next_external_account_id,
+            );
+            assert_eq!(root, legacy_root.encode());
 
-            let wf1_details = profile.get_workflow(wf1_id).unwrap();
-            assert_eq!(wf1_details.commandline, cmd);
-            assert!(wf1_details.enabled);
+            // The external accounts of a profile created before the storage is versioned, i.e.
+            // (id, enabled, account_type, rpc, sk)
+            let key = profile.external_accounts.key();
+            for id in 0..2 as ExternalAccountId {
+                let generated = 1u8;
+                let legacy = (
+                    id,
+                    true,
+                    generated,
+                    String::from("https://testrpc.com"),
+                    [id as u8 + 1; 32],
+                );
+                ink::env::set_contract_storage(&(key, id), &legacy);
+            }
+            profile.next_external_account_id = 2;
+            profile.storage_version.set(&0);
+            assert_eq!(profile.storage_version(), 0);
+            profile.migrate().unwrap();
+            assert_eq!(profile.storage_version(), STORAGE_VERSION);
+            assert_eq!(ink::env::test::recorded_events().count(), events + 1);
+            assert_eq!(
+                profile.get_rpc_endpoints(1).unwrap(),
+                vec![String::from("https://testrpc.com")]
+            );
+            let account = profile.external_accounts.get(1).unwrap();
+            assert_eq!(account.account_type, ExternalAccountType::Generated);
+            assert_eq!(account.sk, [2; 32]);
+            assert_eq!(account.key_type, KeyType::Evm);
+            assert_eq!(account.chain_id, None);
+
+            profile.storage_version.set(&(STORAGE_VERSION + 1));
             assert!(matches!(
-                profile.get_workflow(3),
-                Err(Error::WorkflowNotFound)
+                profile.migrate(),
+                Err(Error::StorageVersionTooNew(_))
             ));
 
-            // Workflow enable and disable
-            let _ = profile.disable_workflow(wf1_id);
-            let wf1_details = profile.get_workflow(wf1_id).unwrap();
-            assert!(!wf1_details.enabled);
-
-            let _ = profile.enable_workflow(wf1_id);
-            let wf1_details = profile.get_workflow(wf1_id).unwrap();
-            assert!(wf1_details.enabled);
-
-            // Access control
             let accounts = ink::env::test::default_accounts::<pink::PinkEnvironment>();
             let contract = ink::env::account_id::<pink::PinkEnvironment>();
             ink::env::test::set_callee::<pink::PinkEnvironment>(contract);
             ink::env::test::set_caller::<pink::PinkEnvironment>(accounts.bob);
-
-            assert!(matches!(
-                profile.add_workflow(name.clone(), cmd.clone()),
-                Err(Error::BadOrigin)
-            ));
             assert!(matches!(
-                profile.enable_workflow(wf1_id),
+                profile.upgrade(Hash::from([1u8; 32])),
                 Err(Error::BadOrigin)
             ));
             assert!(matches!(
-                profile.disable_workflow(wf1_id),
+                profile.set_upgrader(Some(accounts.bob)),
                 Err(Error::BadOrigin)
             ));
+
+            ink::env::test::set_caller::<pink::PinkEnvironment>(accounts.alice);
+            assert_eq!(profile.get_upgrader(), None);
+            profile.set_upgrader(Some(accounts.bob)).unwrap();
+            assert_eq!(profile.get_upgrader(), Some(accounts.bob));
+            profile.set_upgrader(None).unwrap();
+            assert_eq!(profile.get_upgrader(), None);
         }
 
         #[ink::test]
-        fn external_account_management_works() {
+        fn secret_vault_works() {
             let _ = env_logger::try_init();
             pink_extension_runtime::mock_ext::mock_all_ext();
 
-            let EnvVars { rpc, key } = config();
-
             let mut profile = BrickProfile::default();
-
-            // Account generation
-            let ea1_id = profile.generate_evm_account(rpc.clone()).unwrap();
-            let _ = profile.generate_evm_account(rpc.clone()).unwrap();
-            assert_eq!(profile.external_account_count(), 2);
-            let _address = profile.get_evm_account_address(ea1_id).unwrap();
-
-            // RPC update
-            let new_rpc = String::from("https://testrpc.com");
-            let ea1_rpc = profile.get_rpc_endpoint(ea1_id).unwrap();
-            assert_eq!(ea1_rpc, rpc);
-            profile.set_rpc_endpoint(ea1_id, new_rpc.clone()).unwrap();
-            let ea1_new_rpc = profile.get_rpc_endpoint(ea1_id).unwrap();
-            assert_eq!(ea1_new_rpc, new_rpc);
-
-            // Deprecated for first release
+            let cmd = String::from("[]");
+            let wf1_id = profile.add_workflow("wf1".into(), cmd.clone()).unwrap();
+            let wf2_id = profile.add_workflow("wf2".into(), cmd).unwrap();
+            let value =
+                String::from("https://api.lens.dev/?token=0123456789abcdef0123456789abcdef");
+            profile
+                .set_secret("lens_api".into(), value.clone())
+                .unwrap();
             assert!(matches!(
-                profile.import_evm_account(rpc.clone(), key.clone()),
-                Err(Error::Deprecated)
+                profile.set_secret("<script>".into(), value.clone()),
+                Err(Error::InvalidSecretName)
             ));
+            // Encrypted at rest
+            let secret = profile.secrets.get("lens_api").unwrap();
+            assert_eq!(secret.ciphertext.len(), value.len());
+            assert_ne!(secret.ciphertext, value.as_bytes());
 
-            // Account dump
-            let ea2_id = profile.generate_evm_account(rpc.clone()).unwrap();
+            profile.grant_secret("lens_api".into(), wf1_id).unwrap();
             assert!(matches!(
-                profile.get_dumped_key(ea2_id),
-                Err(Error::OnlyDumpedAccount)
+                profile.grant_secret("missing".into(), wf1_id),
+                Err(Error::SecretNotFound)
             ));
-            profile.dump_evm_account(ea2_id).unwrap();
-            let sk = profile.get_dumped_key(ea2_id).unwrap();
-            warn!("Dumped sk: 0x{}", hex::encode(sk));
+            assert_eq!(
+                profile.get_secrets(),
+                vec![SecretInfo {
+                    name: "lens_api".into(),
+                    workflows: vec![wf1_id],
+                }]
+            );
 
-            // Access control
-            let accounts = ink::env::test::default_accounts::<pink::PinkEnvironment>();
+            // Only readable by the granted workflows in their sessions
             let contract = ink::env::account_id::<pink::PinkEnvironment>();
             ink::env::test::set_callee::<pink::PinkEnvironment>(contract);
-            ink::env::test::set_caller::<pink::PinkEnvironment>(accounts.bob);
+            ink::env::test::set_caller::<pink::PinkEnvironment>(contract);
             assert!(matches!(
-                profile.generate_evm_account(rpc.clone()),
-                Err(Error::BadOrigin)
+                profile.get_workflow_secret("lens_api".into()),
+                Err(Error::BadWorkflowSession)
             ));
+            profile.workflow_session.set(&wf1_id);
+            assert_eq!(
+                profile.get_workflow_secret("lens_api".into()).unwrap(),
+                value
+            );
+            profile.workflow_session.set(&wf2_id);
             assert!(matches!(
-                profile.get_rpc_endpoint(ea1_id),
-                Err(Error::BadOrigin)
+                profile.get_workflow_secret("lens_api".into()),
+                Err(Error::SecretNotGranted)
             ));
+
+            // Overwriting keeps the grants
+            let accounts = ink::env::test::default_accounts::<pink::PinkEnvironment>();
+            ink::env::test::set_caller::<pink::PinkEnvironment>(accounts.alice);
+            profile.set_secret("lens_api".into(), "new".into()).unwrap();
+            profile.workflow_session.set(&wf1_id);
+            assert_eq!(
+                profile.get_workflow_secret("lens_api".into()).unwrap(),
+                "new"
+            );
+
+            profile.revoke_secret("lens_api".into(), wf1_id).unwrap();
             assert!(matches!(
-                profile.set_rpc_endpoint(ea1_id, new_rpc.clone()),
+                profile.get_workflow_secret("lens_api".into()),
+                Err(Error::SecretNotGranted)
+            ));
+            profile.remove_secret("lens_api".into()).unwrap();
+            assert!(profile.get_secrets().is_empty());
+            assert!(matches!(
+                profile.get_workflow_secret("lens_api".into()),
+                Err(Error::SecretNotFound)
+            ));
+
+            ink::env::test::set_caller::<pink::PinkEnvironment>(accounts.bob);
+            assert!(matches!(
+                profile.set_secret("lens_api".into(), value.clone()),
                 Err(Error::BadOrigin)
             ));
+
+            // The grants are revoked once an operator changes the commandline
+            ink::env::test::set_caller::<pink::PinkEnvironment>(accounts.alice);
+            profile
+                .grant_operator_role(accounts.bob, OperatorRole::WorkflowEditor)
+                .unwrap();
+            profile.set_secret("lens_api".into(), value).unwrap();
+            profile.grant_secret("lens_api".into(), wf2_id).unwrap();
+            let new_cmd = String::from("[{\"cmd\": \"log\"}]");
+            profile
+                .update_workflow(wf2_id, "wf2".into(), new_cmd.clone())
+                .unwrap();
+            assert_eq!(profile.get_secrets()[0].workflows, vec![wf2_id]);
+            ink::env::test::set_caller::<pink::PinkEnvironment>(accounts.bob);
+            profile
+                .update_workflow(wf2_id, "renamed".into(), new_cmd)
+                .unwrap();
+            assert_eq!(profile.get_secrets()[0].workflows, vec![wf2_id]);
+            profile
+                .update_workflow(wf2_id, "wf2".into(), "[]".into())
+                .unwrap();
+            assert!(profile.get_secrets()[0].workflows.is_empty());
         }
 
         #[ink::test]
-        fn workflow_auth_works() {
+        fn workflow_runner_works() {
             let _ = env_logger::try_init();
             pink_extension_runtime::mock_ext::mock_all_ext();
 
-            let EnvVars { rpc, key: _ } = config();
-
+            let accounts = ink::env::test::default_accounts::<pink::PinkEnvironment>();
             let mut profile = BrickProfile::default();
+            let cmd = String::from("[{\"cmd\": \"log\"}]");
+            let wf_id = profile.add_workflow("Test".into(), cmd).unwrap();
 
-            let cmd = String::from("[
-                {\"cmd\": \"fetch\", \"config\": {\"returnTextBody\":true,\"url\":\"https://min-api.cryptocompare.com/data/price?fsym=ETH&tsyms=BTC,USD,EUR\"}},
-                {\"cmd\": \"eval\", \"config\": \"Math.round(JSON.parse(input.body).USD)\"},
-                {\"cmd\": \"eval\", \"config\": \"numToUint8Array32(input)\"},
-            ]");
-            let name = String::from("TestWorkflow");
-
-            let wf1_id = profile.add_workflow(name.clone(), cmd.clone()).unwrap();
-            let wf2_id = profile.add_workflow(name.clone(), cmd.clone()).unwrap();
-            let wf3_id = profile.add_workflow(name.clone(), cmd.clone()).unwrap();
-            let ea1_id = profile.generate_evm_account(rpc.clone()).unwrap();
-            let ea2_id = profile.generate_evm_account(rpc.clone()).unwrap();
+            assert!(matches!(
+                profile.get_workflow_runner(wf_id),
+                Err(Error::NotConfigured)
+            ));
+            profile.config(accounts.django).unwrap();
+            assert_eq!(
+                profile.get_workflow_runner(wf_id).unwrap(),
+                WorkflowRunner {
+                    contract: accounts.django,
+                    selector: ink::selector_bytes!("run"),
+                    with_commandline: true,
+                    output: RunnerOutput::Bool,
+                }
+            );
 
-            let workflow_accounts =
-                BTreeMap::from([(wf1_id, ea1_id), (wf2_id, ea2_id), (wf3_id, ea2_id)]);
+            // Overrides the js_runner
+            let runner = WorkflowRunner {
+                contract: accounts.eve,
+                selector: ink::selector_bytes!("answer_request"),
+                with_commandline: false,
+                output: RunnerOutput::ResultOptionBytes,
+            };
+            profile
+                .set_workflow_runner(wf_id, Some(runner.clone()))
+                .unwrap();
+            assert_eq!(profile.get_workflow_runner(wf_id).unwrap(), runner);
+            assert_eq!(
+                profile.get_workflow(wf_id).unwrap().runner,
+                Some(runner.clone())
+            );
+            // Commandlines not passed to the runner are not validated
+            profile
+                .update_workflow(wf_id, "Test".into(), "custom".into())
+                .unwrap();
+            let custom_id = profile
+                .add_workflow_with_runner("Custom".into(), "custom".into(), Some(runner.clone()))
+                .unwrap();
+            assert_eq!(profile.get_workflow_runner(custom_id).unwrap(), runner);
+            assert!(matches!(
+                profile.add_workflow_with_runner("Custom".into(), "custom".into(), None),
+                Err(Error::InvalidCommandline { .. })
+            ));
 
-            for (wf_id, ea_id) in workflow_accounts.iter() {
-                profile
-                    .authorize_workflow(wf_id.clone(), ea_id.clone())
-                    .unwrap();
-                assert_eq!(
-                    profile.get_authorized_account(wf_id.clone()).unwrap(),
-                    ea_id.clone()
-                );
-            }
+            // Not clearing the override with a commandline the js_runner can not run
+            assert!(matches!(
+                profile.set_workflow_runner(wf_id, None),
+                Err(Error::InvalidCommandline { .. })
+            ));
+            assert_eq!(profile.get_workflow_runner(wf_id).unwrap(), runner);
+            profile
+                .update_workflow(wf_id, "Test".into(), "[]".into())
+                .unwrap();
+            profile.set_workflow_runner(wf_id, None).unwrap();
+            assert_eq!(
+                profile.get_workflow_runner(wf_id).unwrap().contract,
+                accounts.django
+            );
+            assert!(matches!(
+                profile.update_workflow(wf_id, "Test".into(), "custom".into()),
+                Err(Error::InvalidCommandline { .. })
+            ));
+            assert!(matches!(
+                profile.set_workflow_runner(100, None),
+                Err(Error::WorkflowNotFound)
+            ));
 
-            // Test dynamic authorization
             let contract = ink::env::account_id::<pink::PinkEnvironment>();
             ink::env::test::set_callee::<pink::PinkEnvironment>(contract);
-            ink::env::test::set_caller::<pink::PinkEnvironment>(contract);
+            ink::env::test::set_caller::<pink::PinkEnvironment>(accounts.bob);
+            assert!(matches!(
+                profile.set_workflow_runner(wf_id, None),
+                Err(Error::BadOrigin)
+            ));
 
-            for (wf_id, ea_id) in workflow_accounts.iter() {
-                profile.workflow_session.set(&wf_id);
-                let current_evm_address = profile.get_current_evm_account_address().unwrap();
-                let expected_evm_address = profile.get_evm_account_address(ea_id.clone()).unwrap();
-                assert_eq!(current_evm_address, expected_evm_address);
-            }
+            // The runner replies are decoded in full whatever the error type is
+            let decode = |reply: Vec<u8>| {
+                let output: ink::MessageResult<RawOutput> =
+                    scale::DecodeAll::decode_all(&mut &reply[..]).unwrap();
+                output.unwrap().0.first() == Some(&0)
+            };
+            let ok = Ok::<_, (u8, String)>(Some(vec![1u8, 2]));
+            assert!(decode(Ok::<_, ink::LangError>(ok).encode()));
+            let err = Err::<Option<Vec<u8>>, _>((3u8, String::from("bad request")));
+            assert!(!decode(Ok::<_, ink::LangError>(err).encode()));
         }
     }
 }
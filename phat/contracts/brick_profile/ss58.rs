@@ -0,0 +1,63 @@
+//! SS58 address encoding of Substrate public keys.
+//!
+//! See https://docs.substrate.io/reference/address-formats/
+
+use alloc::{string::String, vec::Vec};
+use blake2::{Blake2b512, Digest};
+
+const CHECKSUM_CONTEXT: &[u8] = b"SS58PRE";
+const CHECKSUM_LEN: usize = 2;
+const ALPHABET: &[u8; 58] = b"123456789ABCDEFGHJKLMNPQRSTUVWXYZabcdefghijkmnopqrstuvwxyz";
+
+/// The largest network prefix encodable in an SS58 address.
+pub const MAX_PREFIX: u16 = 16383;
+
+/// Encodes the public key as an SS58 address of the network `prefix`.
+///
+/// Returns `None` if the prefix is larger than `MAX_PREFIX`.
+pub fn encode(prefix: u16, public_key: &[u8]) -> Option<String> {
+    let mut data = match prefix {
+        0..=63 => Vec::from([prefix as u8]),
+        64..=MAX_PREFIX => {
+            // The lower 6 bits of the first byte and the whole second byte carry the prefix
+            let first = ((prefix & 0b0000_0000_1111_1100) >> 2) as u8 | 0b0100_0000;
+            let second = (prefix >> 8) as u8 | ((prefix & 0b0000_0000_0000_0011) << 6) as u8;
+            Vec::from([first, second])
+        }
+        _ => return None,
+    };
+    data.extend_from_slice(public_key);
+    let checksum = Blake2b512::new()
+        .chain_update(CHECKSUM_CONTEXT)
+        .chain_update(&data)
+        .finalize();
+    data.extend_from_slice(&checksum[..CHECKSUM_LEN]);
+    Some(base58_encode(&data))
+}
+
+fn base58_encode(input: &[u8]) -> String {
+    // Little-endian digits in base 58
+    let mut digits: Vec<u8> = Vec::with_capacity(input.len() * 138 / 100 + 1);
+    for &byte in input {
+        let mut carry = byte as u32;
+        for digit in digits.iter_mut() {
+            carry += (*digit as u32) << 8;
+            *digit = (carry % 58) as u8;
+            carry /= 58;
+        }
+        while carry > 0 {
+            digits.push((carry % 58) as u8);
+            carry /= 58;
+        }
+    }
+    let leading_zeros = input.iter().take_while(|&&byte| byte == 0).count();
+    let mut encoded = String::with_capacity(leading_zeros + digits.len());
+    encoded.extend((0..leading_zeros).map(|_| char::from(ALPHABET[0])));
+    encoded.extend(
+        digits
+            .iter()
+            .rev()
+            .map(|&digit| char::from(ALPHABET[digit as usize])),
+    );
+    encoded
+}
@@ -0,0 +1,32 @@
+//! Encryption of the secrets in the vault at rest.
+//!
+//! A secret is XOR-ed with the keystream of 32-byte blocks `SHA-256(key || nonce || counter)`.
+//! The nonce is random for each write so that a keystream is never reused. Secrets are only
+//! written by the owner of the profile, so they are not authenticated.
+
+use alloc::vec::Vec;
+use ink::env::hash::{HashOutput, Sha2x256};
+
+pub const KEY_LEN: usize = 32;
+pub const NONCE_LEN: usize = 16;
+
+/// Encrypts or decrypts the data, which are the same operation.
+pub fn apply_keystream(key: &[u8; KEY_LEN], nonce: &[u8; NONCE_LEN], data: &[u8]) -> Vec<u8> {
+    data.chunks(KEY_LEN)
+        .enumerate()
+        .flat_map(|(counter, chunk)| {
+            let block = sha256(&[&key[..], &nonce[..], &(counter as u32).to_be_bytes()].concat());
+            chunk
+                .iter()
+                .zip(block)
+                .map(|(byte, key_byte)| byte ^ key_byte)
+                .collect::<Vec<_>>()
+        })
+        .collect()
+}
+
+fn sha256(input: &[u8]) -> [u8; KEY_LEN] {
+    let mut output = <Sha2x256 as HashOutput>::Type::default();
+    ink::env::hash_bytes::<Sha2x256>(input, &mut output);
+    output
+}
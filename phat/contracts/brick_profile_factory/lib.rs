@@ -6,21 +6,112 @@ pub use brick_profile_factory::*;
 
 #[ink::contract(env = pink::PinkEnvironment)]
 mod brick_profile_factory {
-    use alloc::{collections::BTreeMap, format, string::String, vec::Vec};
+    use alloc::{format, string::String, vec::Vec};
     use brick_profile::BrickProfileRef;
+    use ink::codegen::TraitCallBuilder;
     use ink::env::call::FromAccountId;
+    use ink::env::hash::{Blake2x256, HashOutput};
+    #[cfg(feature = "std")]
+    use ink::storage::traits::StorageLayout;
+    use ink::storage::{Lazy, Mapping};
     use ink::ToAccountId;
+    use ownership::{Ownable, Ownership};
     use pink_extension as pink;
-    use pink_extension::chain_extension::signing;
     use scale::{Decode, Encode};
     use this_crate::{version_tuple, VersionTuple};
 
+    /// The label of the profiles created by the unlabeled messages
+    pub const DEFAULT_LABEL: &str = "default";
+    const MAX_LABEL_LEN: usize = 32;
+    const PROFILE_SALT_PREFIX: &[u8] = b"brick_profile";
+
+    /// Emitted when the ownership is accepted or renounced, `None` means no owner.
+    #[ink(event)]
+    pub struct OwnershipTransferred {
+        #[ink(topic)]
+        pub previous_owner: Option<AccountId>,
+        #[ink(topic)]
+        pub new_owner: Option<AccountId>,
+    }
+
+    /// A named bundle of the setup applied to the profiles created from it.
+    #[derive(Encode, Decode, Debug, Clone, PartialEq)]
+    #[cfg_attr(feature = "std", derive(scale_info::TypeInfo, StorageLayout))]
+    pub struct ProfileTemplate {
+        pub js_runner: AccountId,
+        /// The rpc endpoints of each EVM account to generate, in the order of preference
+        pub accounts: Vec<Vec<String>>,
+        pub workflows: Vec<TemplateWorkflow>,
+    }
+
+    #[derive(Encode, Decode, Debug, Clone, PartialEq)]
+    #[cfg_attr(feature = "std", derive(scale_info::TypeInfo, StorageLayout))]
+    pub struct TemplateWorkflow {
+        pub name: String,
+        pub commandline: String,
+        /// The (role, index of the account in `ProfileTemplate::accounts`) bindings
+        pub bindings: Vec<(String, u32)>,
+    }
+
+    /// The changes to a template for a single profile.
+    #[derive(Encode, Decode, Debug, Clone, Default, PartialEq)]
+    #[cfg_attr(feature = "std", derive(scale_info::TypeInfo))]
+    pub struct TemplateOverrides {
+        /// The default label is used if not set
+        pub label: Option<String>,
+        pub js_runner: Option<AccountId>,
+        /// Replaces the rpc endpoints of the account at the index
+        pub rpcs: Vec<(u32, Vec<String>)>,
+    }
+
+    /// A rollout of a profile code hash to the registered profiles.
+    #[derive(Encode, Decode, Debug, Clone, PartialEq)]
+    #[cfg_attr(feature = "std", derive(scale_info::TypeInfo, StorageLayout))]
+    pub struct UpgradeRollout {
+        pub code_hash: Hash,
+        /// The index of the next user to upgrade in the order of registration
+        pub next_user: u64,
+    }
+
+    #[derive(Encode, Decode, Debug, Clone, PartialEq)]
+    #[cfg_attr(feature = "std", derive(scale_info::TypeInfo, StorageLayout))]
+    pub enum UpgradeStatus {
+        /// Not reached by the rollout yet
+        Pending,
+        Upgraded,
+        /// The profile owner has not allowed the factory to upgrade with `set_upgrader`
+        NotOptedIn,
+        /// The profile is too old to be upgraded, i.e. it has no `upgrade` message
+        Unsupported,
+        Failed(String),
+    }
+
+    /// The factory can not be upgraded in place. The profiles of an older factory are moved over
+    /// by importing the pages of its `get_user_profiles` with `import_user_profiles`.
     #[ink(storage)]
     pub struct BrickProfileFactory {
-        owner: AccountId,
+        ownership: Ownership,
         profile_code_hash: Hash,
-        nonce: u64,
-        users: BTreeMap<AccountId, BrickProfileRef>,
+        /// The profile of each (user, label)
+        profiles: Mapping<(AccountId, String), AccountId>,
+        /// The labels of the profiles of each user in the order they are created
+        user_labels: Mapping<AccountId, Vec<String>>,
+        /// The owner of each registered profile, including the archived ones
+        profile_owners: Mapping<AccountId, AccountId>,
+        /// The profiles of each user replaced by newer ones with the same label
+        archived_profiles: Mapping<AccountId, Vec<AccountId>>,
+        /// The users in the order they are registered, the index is never shrunk
+        user_index: Mapping<u64, AccountId>,
+        indexed_users: Lazy<u64>,
+        templates: Mapping<String, ProfileTemplate>,
+        template_names: Lazy<Vec<String>>,
+        upgrade_rollout: Lazy<UpgradeRollout>,
+        /// The status of each profile reached by a rollout, with the code hash of the rollout
+        upgrade_status: Mapping<AccountId, (Hash, UpgradeStatus)>,
+        /// The times the profile of each (user, label) is replaced, which is salted in
+        profile_generations: Mapping<(AccountId, String), u32>,
+        /// The cluster the factory is deployed in, which the profile addresses depend on
+        cluster_id: Lazy<Hash>,
     }
 
     #[derive(Encode, Decode, Debug)]
@@ -30,6 +121,11 @@ mod brick_profile_factory {
         NoDuplicatedUserProfile,
         FailedToCreateProfile(String),
         UserProfileNotExists,
+        InvalidLabel,
+        InvalidTemplate(String),
+        TemplateNotFound,
+        NoUpgradeScheduled,
+        ClusterIdNotSet,
     }
     pub type Result<T> = core::result::Result<T, Error>;
 
@@ -38,10 +134,20 @@ mod brick_profile_factory {
         pub fn new(profile_code_hash: Hash) -> Self {
             let caller = Self::env().caller();
             Self {
-                owner: caller,
+                ownership: Ownership::new(caller),
                 profile_code_hash,
-                nonce: 0,
-                users: Default::default(),
+                profiles: Mapping::default(),
+                user_labels: Mapping::default(),
+                profile_owners: Mapping::default(),
+                archived_profiles: Mapping::default(),
+                user_index: Mapping::default(),
+                indexed_users: Default::default(),
+                templates: Mapping::default(),
+                template_names: Default::default(),
+                upgrade_rollout: Default::default(),
+                upgrade_status: Mapping::default(),
+                profile_generations: Mapping::default(),
+                cluster_id: Default::default(),
             }
         }
 
@@ -50,16 +156,10 @@ mod brick_profile_factory {
             version_tuple!()
         }
 
-        /// Get the owner of the contract, only owner can change the profile contract code hash.
-        #[ink(message)]
-        pub fn owner(&self) -> AccountId {
-            self.owner
-        }
-
         /// Get the user profile number.
         #[ink(message)]
         pub fn user_count(&self) -> u64 {
-            self.users.len() as u64
+            self.indexed_users()
         }
 
         /// Get the code hash of the user profile contract.
@@ -76,61 +176,202 @@ mod brick_profile_factory {
             Ok(())
         }
 
+        /// Roll out the code hash to the registered profiles, and use it for new profiles (only
+        /// owner). The profiles are upgraded in batches by `upgrade_profiles`.
+        /// Only the profiles whose owners have opted in by setting the factory as the upgrader
+        /// are upgraded.
+        #[ink(message)]
+        pub fn schedule_profile_upgrade(&mut self, code_hash: Hash) -> Result<()> {
+            self.ensure_owner()?;
+            self.profile_code_hash = code_hash;
+            self.upgrade_rollout.set(&UpgradeRollout {
+                code_hash,
+                next_user: 0,
+            });
+            Ok(())
+        }
+
+        /// Get the latest rollout scheduled by `schedule_profile_upgrade`.
+        #[ink(message)]
+        pub fn get_upgrade_rollout(&self) -> Option<UpgradeRollout> {
+            self.upgrade_rollout.get()
+        }
+
+        /// Upgrade the profiles of the next users of the rollout (only owner).
+        /// A failed profile does not affect the others, see `get_upgrade_status`.
+        /// Return the number of users left.
+        #[ink(message)]
+        pub fn upgrade_profiles(&mut self, limit: u64) -> Result<u64> {
+            self.ensure_owner()?;
+            let mut rollout = self
+                .upgrade_rollout
+                .get()
+                .ok_or(Error::NoUpgradeScheduled)?;
+            let end = self
+                .indexed_users()
+                .min(rollout.next_user.saturating_add(limit));
+            for index in rollout.next_user..end {
+                let Some(user) = self.user_index.get(index) else {
+                    continue;
+                };
+                for (_, profile) in self.user_profiles(user) {
+                    let status = match self.env().code_hash(&profile) {
+                        Ok(code_hash) if code_hash == rollout.code_hash => UpgradeStatus::Upgraded,
+                        _ => upgrade_profile(profile, rollout.code_hash),
+                    };
+                    self.upgrade_status
+                        .insert(profile, &(rollout.code_hash, status));
+                }
+            }
+            rollout.next_user = end;
+            self.upgrade_rollout.set(&rollout);
+            Ok(self.indexed_users() - end)
+        }
+
+        /// Get the upgrade status of the profile in the latest rollout.
+        /// Return None if no upgrade is scheduled or the profile is not created by the factory.
+        #[ink(message)]
+        pub fn get_upgrade_status(&self, profile: AccountId) -> Option<UpgradeStatus> {
+            let rollout = self.upgrade_rollout.get()?;
+            if !self.profile_owners.contains(profile) {
+                return None;
+            }
+            match self.upgrade_status.get(profile) {
+                Some((code_hash, status)) if code_hash == rollout.code_hash => Some(status),
+                _ => Some(UpgradeStatus::Pending),
+            }
+        }
+
+        /// Get the id of the cluster the factory is deployed in.
+        #[ink(message)]
+        pub fn cluster_id(&self) -> Option<Hash> {
+            self.cluster_id.get()
+        }
+
+        /// Set the id of the cluster the factory is deployed in (only owner).
+        /// It is required by `predict_profile_address`.
+        #[ink(message)]
+        pub fn set_cluster_id(&mut self, cluster_id: Hash) -> Result<()> {
+            self.ensure_owner()?;
+            self.cluster_id.set(&cluster_id);
+            Ok(())
+        }
+
+        /// Predict the address of the profile with the label of the owner.
+        /// This is the address the next profile with the label is created at by the current
+        /// profile code hash, or the existing one if it is created by the same code hash.
+        #[ink(message)]
+        pub fn predict_profile_address(
+            &self,
+            owner: AccountId,
+            label: String,
+        ) -> Result<AccountId> {
+            ensure_valid_label(&label)?;
+            let cluster_id = self.cluster_id.get().ok_or(Error::ClusterIdNotSet)?;
+            let salt = profile_salt(&owner, &label, self.profile_generation(owner, &label));
+            Ok(contract_address(
+                &self.env().account_id(),
+                &self.profile_code_hash,
+                &cluster_id,
+                &salt,
+            ))
+        }
+
         /// Import user profile addresses (only owner). This will overwrite existing addresses.
+        /// The profiles are imported with the default label.
+        ///
+        /// Used to move over the users of a factory deployed before the registry is paginated, which
+        /// can not be upgraded in place. Import the `get_user_profiles()` output of the old factory,
+        /// in batches if it is large, then check `user_count()` matches the old one. See the
+        /// README for the procedure.
         #[ink(message)]
         pub fn import_user_profiles(
             &mut self,
             profiles: Vec<(AccountId, AccountId)>,
         ) -> Result<()> {
             self.ensure_owner()?;
-            self.users.extend(
-                profiles
-                    .into_iter()
-                    .map(|kv| (kv.0, BrickProfileRef::from_account_id(kv.1.into()))),
-            );
+            for (user, profile) in profiles {
+                self.register_profile(user, DEFAULT_LABEL, profile);
+            }
             Ok(())
         }
 
         /// Instantiate a user profile contract for caller, overwrite the existing one.
-        /// Return old user profile contract if there is.
+        /// Return old user profile contract if there is, which is archived.
         #[ink(message)]
         pub fn force_create_user_profile(&mut self) -> Result<Option<AccountId>> {
-            let caller = self.env().caller();
+            self.force_create_labeled_profile(DEFAULT_LABEL.into())
+        }
+
+        /// Instantiate a user profile contract for caller. Only once for each account.
+        #[ink(message)]
+        pub fn create_user_profile(&mut self) -> Result<()> {
+            self.create_labeled_profile(DEFAULT_LABEL.into())?;
+            Ok(())
+        }
+
+        /// Instantiate and setup a user profile contract for caller, overwrite the existing one.
+        /// This configures the js runner and generates the first evm account.
+        /// Return old user profile contract if there is, which is archived.
+        #[ink(message)]
+        pub fn force_setup_user_profile(
+            &mut self,
+            js_runner: AccountId,
+            rpc: String,
+        ) -> Result<Option<AccountId>> {
+            self.force_setup_labeled_profile(DEFAULT_LABEL.into(), js_runner, rpc)
+        }
+
+        /// Instantiate and setup a user profile contract for caller. Only once for each account.
+        /// This configures the js runner and generates the first evm account.
+        #[ink(message)]
+        pub fn setup_user_profile(&mut self, js_runner: AccountId, rpc: String) -> Result<()> {
+            self.setup_labeled_profile(DEFAULT_LABEL.into(), js_runner, rpc)?;
+            Ok(())
+        }
 
-            let user_profile = self.instantiate_profile()?;
+        /// Instantiate a user profile contract with the label for caller, overwrite the existing
+        /// one with the same label.
+        /// Return old user profile contract if there is, which is archived.
+        #[ink(message)]
+        pub fn force_create_labeled_profile(&mut self, label: String) -> Result<Option<AccountId>> {
+            ensure_valid_label(&label)?;
+            let caller = self.env().caller();
 
-            let old_user_profile = self
-                .users
-                .insert(caller, user_profile)
-                .map(|p| p.to_account_id());
+            let user_profile = self.instantiate_profile(&label)?;
 
-            Ok(old_user_profile)
+            Ok(self.register_profile(caller, &label, user_profile.to_account_id()))
         }
 
-        /// Instantiate a user profile contract for caller. Only once for each account.
+        /// Instantiate a user profile contract with the label for caller. Only once for each label.
+        /// Return the new user profile contract.
         #[ink(message)]
-        pub fn create_user_profile(&mut self) -> Result<()> {
+        pub fn create_labeled_profile(&mut self, label: String) -> Result<AccountId> {
             let caller = self.env().caller();
-            if self.users.contains_key(&caller) {
+            if self.profile_of(caller, &label).is_some() {
                 return Err(Error::NoDuplicatedUserProfile);
             }
 
-            self.force_create_user_profile()?;
-            Ok(())
+            self.force_create_labeled_profile(label.clone())?;
+            self.profile_of(caller, &label)
+                .ok_or(Error::UserProfileNotExists)
         }
 
-        /// Instantiate and setup a user profile contract for caller, overwrite the existing one.
+        /// Instantiate and setup a user profile contract with the label for caller, overwrite the
+        /// existing one with the same label.
         /// This configures the js runner and generates the first evm account.
-        /// Return old user profile contract if there is.
+        /// Return old user profile contract if there is, which is archived.
         #[ink(message)]
-        pub fn force_setup_user_profile(
+        pub fn force_setup_labeled_profile(
             &mut self,
+            label: String,
             js_runner: AccountId,
             rpc: String,
         ) -> Result<Option<AccountId>> {
+            ensure_valid_label(&label)?;
             let caller = self.env().caller();
 
-            let mut user_profile = self.instantiate_profile()?;
+            let mut user_profile = self.instantiate_profile(&label)?;
             user_profile
                 .config(js_runner)
                 .map_err(|e| Error::FailedToCreateProfile(format!("{:?}", e)))?;
@@ -138,79 +379,720 @@ mod brick_profile_factory {
                 .generate_evm_account(rpc)
                 .map_err(|e| Error::FailedToCreateProfile(format!("{:?}", e)))?;
 
-            let old_user_profile = self
-                .users
-                .insert(caller, user_profile)
-                .map(|p| p.to_account_id());
-
-            Ok(old_user_profile)
+            Ok(self.register_profile(caller, &label, user_profile.to_account_id()))
         }
 
-        /// Instantiate and setup a user profile contract for caller. Only once for each account.
+        /// Instantiate and setup a user profile contract with the label for caller. Only once for
+        /// each label.
         /// This configures the js runner and generates the first evm account.
+        /// Return the new user profile contract.
         #[ink(message)]
-        pub fn setup_user_profile(&mut self, js_runner: AccountId, rpc: String) -> Result<()> {
+        pub fn setup_labeled_profile(
+            &mut self,
+            label: String,
+            js_runner: AccountId,
+            rpc: String,
+        ) -> Result<AccountId> {
             let caller = self.env().caller();
-            if self.users.contains_key(&caller) {
+            if self.profile_of(caller, &label).is_some() {
                 return Err(Error::NoDuplicatedUserProfile);
             }
 
-            self.force_setup_user_profile(js_runner, rpc)?;
+            self.force_setup_labeled_profile(label.clone(), js_runner, rpc)?;
+            self.profile_of(caller, &label)
+                .ok_or(Error::UserProfileNotExists)
+        }
+
+        /// Add or replace a profile template (only owner).
+        #[ink(message)]
+        pub fn set_template(&mut self, name: String, template: ProfileTemplate) -> Result<()> {
+            self.ensure_owner()?;
+            ensure_valid_label(&name).or(Err(Error::InvalidTemplate("invalid name".into())))?;
+            ensure_valid_template(&template)?;
+            if !self.templates.contains(&name) {
+                let mut names = self.template_names.get().unwrap_or_default();
+                names.push(name.clone());
+                self.template_names.set(&names);
+            }
+            self.templates.insert(&name, &template);
             Ok(())
         }
 
+        /// Remove a profile template (only owner).
+        #[ink(message)]
+        pub fn remove_template(&mut self, name: String) -> Result<()> {
+            self.ensure_owner()?;
+            if !self.templates.contains(&name) {
+                return Err(Error::TemplateNotFound);
+            }
+            self.templates.remove(&name);
+            let mut names = self.template_names.get().unwrap_or_default();
+            names.retain(|n| *n != name);
+            self.template_names.set(&names);
+            Ok(())
+        }
+
+        /// Get the profile template.
+        #[ink(message)]
+        pub fn get_template(&self, name: String) -> Result<ProfileTemplate> {
+            self.templates.get(&name).ok_or(Error::TemplateNotFound)
+        }
+
+        /// Get the names of the profile templates.
+        #[ink(message)]
+        pub fn get_template_names(&self) -> Vec<String> {
+            self.template_names.get().unwrap_or_default()
+        }
+
+        /// Instantiate a user profile contract for caller and apply the template with the
+        /// overrides. Only once for each label.
+        /// This configures the js runner, generates the EVM accounts and adds the workflows
+        /// authorized to them. Nothing is created if any step fails.
+        /// Return the new user profile contract.
+        #[ink(message)]
+        pub fn setup_user_profile_from_template(
+            &mut self,
+            name: String,
+            overrides: TemplateOverrides,
+        ) -> Result<AccountId> {
+            let mut template = self.get_template(name)?;
+            if let Some(js_runner) = overrides.js_runner {
+                template.js_runner = js_runner;
+            }
+            for (index, rpcs) in overrides.rpcs {
+                let account = template
+                    .accounts
+                    .get_mut(index as usize)
+                    .ok_or_else(|| Error::InvalidTemplate(format!("no account {index}")))?;
+                *account = rpcs;
+            }
+            ensure_valid_template(&template)?;
+            let label = overrides.label.unwrap_or_else(|| DEFAULT_LABEL.into());
+            ensure_valid_label(&label)?;
+            let caller = self.env().caller();
+            if self.profile_of(caller, &label).is_some() {
+                return Err(Error::NoDuplicatedUserProfile);
+            }
+
+            let to_error =
+                |e: brick_profile::Error| Error::FailedToCreateProfile(format!("{:?}", e));
+            let mut user_profile = self.instantiate_profile(&label)?;
+            user_profile.config(template.js_runner).map_err(to_error)?;
+            let mut account_ids = Vec::new();
+            for rpcs in template.accounts {
+                let id = user_profile
+                    .generate_evm_account(rpcs[0].clone())
+                    .map_err(to_error)?;
+                if rpcs.len() > 1 {
+                    user_profile.set_rpc_endpoints(id, rpcs).map_err(to_error)?;
+                }
+                account_ids.push(id);
+            }
+            for workflow in template.workflows {
+                let workflow_id = user_profile
+                    .add_workflow(workflow.name, workflow.commandline)
+                    .map_err(to_error)?;
+                for (role, index) in workflow.bindings {
+                    user_profile
+                        .authorize_workflow_role(workflow_id, role, account_ids[index as usize])
+                        .map_err(to_error)?;
+                }
+            }
+
+            let profile = user_profile.to_account_id();
+            self.register_profile(caller, &label, profile);
+            Ok(profile)
+        }
+
         /// Get the user profile contract address.
         #[ink(message)]
         pub fn get_user_profile_address(&self) -> Result<AccountId> {
+            self.get_labeled_profile_address(DEFAULT_LABEL.into())
+        }
+
+        /// Get the address of the user profile contract with the label.
+        #[ink(message)]
+        pub fn get_labeled_profile_address(&self, label: String) -> Result<AccountId> {
             let caller = self.env().caller();
-            let user_profile = self.users.get(&caller).ok_or(Error::UserProfileNotExists)?;
-            Ok(user_profile.to_account_id())
+            self.profile_of(caller, &label)
+                .ok_or(Error::UserProfileNotExists)
         }
 
-        /// Get the user profile contract list.
+        /// Get the (label, profile) list of the user in the order of creation.
         #[ink(message)]
-        pub fn get_user_profiles(&self) -> Result<Vec<(AccountId, AccountId)>> {
-            let profiles = self
-                .users
-                .clone()
-                .into_iter()
-                .map(|kv| (kv.0, kv.1.to_account_id()))
+        pub fn get_profiles_of(&self, user: AccountId) -> Vec<(String, AccountId)> {
+            self.user_profiles(user)
+        }
+
+        /// Get the user owning the profile, archived profiles included.
+        #[ink(message)]
+        pub fn get_profile_owner(&self, profile: AccountId) -> Option<AccountId> {
+            self.profile_owners.get(profile)
+        }
+
+        /// Get the profiles of the user replaced by newer ones, the oldest first.
+        #[ink(message)]
+        pub fn get_archived_profiles(&self, user: AccountId) -> Vec<AccountId> {
+            self.archived_profiles.get(user).unwrap_or_default()
+        }
+
+        /// Get the (user, label, profile) list of the users in the range, in the order of
+        /// registration.
+        #[ink(message)]
+        pub fn get_user_profiles(
+            &self,
+            offset: u64,
+            limit: u64,
+        ) -> Result<Vec<(AccountId, String, AccountId)>> {
+            let end = self.indexed_users().min(offset.saturating_add(limit));
+            let profiles = (offset..end)
+                .filter_map(|index| self.user_index.get(index))
+                .flat_map(|user| {
+                    self.user_profiles(user)
+                        .into_iter()
+                        .map(move |(label, profile)| (user, label, profile))
+                })
                 .collect();
             Ok(profiles)
         }
 
-        fn instantiate_profile(&mut self) -> Result<BrickProfileRef> {
+        /// Get the user profiles not running the current profile code hash among the registered
+        /// users in the range, see `get_user_profiles`.
+        /// Return (user, profile, code hash) of each, the profiles no longer alive are skipped.
+        #[ink(message)]
+        pub fn get_outdated_profiles(
+            &self,
+            offset: u64,
+            limit: u64,
+        ) -> Result<Vec<(AccountId, AccountId, Hash)>> {
+            let end = self.indexed_users().min(offset.saturating_add(limit));
+            let profiles = (offset..end)
+                .filter_map(|index| self.user_index.get(index))
+                .flat_map(|user| {
+                    self.user_profiles(user)
+                        .into_iter()
+                        .map(move |(_, profile)| (user, profile))
+                })
+                .filter_map(|(user, profile)| {
+                    let code_hash = self.env().code_hash(&profile).ok()?;
+                    (code_hash != self.profile_code_hash).then_some((user, profile, code_hash))
+                })
+                .collect();
+            Ok(profiles)
+        }
+
+        /// Instantiate a profile for caller at the address derived from caller and the label.
+        /// A replaced profile takes the next generation of the label, so the address is new.
+        fn instantiate_profile(&mut self, label: &str) -> Result<BrickProfileRef> {
             let caller = self.env().caller();
 
-            let random = signing::derive_sr25519_key(&self.nonce.to_be_bytes());
+            let mut generation = self.profile_generation(caller, label);
+            if self.profile_of(caller, label).is_some() {
+                generation += 1;
+                self.profile_generations
+                    .insert((caller, label), &generation);
+            }
+            let salt = profile_salt(&caller, label, generation);
             let user_profile = BrickProfileRef::new(caller)
                 .endowment(0)
-                .salt_bytes(&random[..4])
+                .salt_bytes(&salt)
                 .code_hash(self.profile_code_hash)
                 .try_instantiate()
                 .map_err(|e| Error::FailedToCreateProfile(format!("{:?}", e)))?
                 .map_err(|e| Error::FailedToCreateProfile(format!("{:?}", e)))?;
-            self.nonce += 1;
 
             Ok(user_profile)
         }
 
+        fn profile_generation(&self, user: AccountId, label: &str) -> u32 {
+            self.profile_generations
+                .get((user, label))
+                .unwrap_or_default()
+        }
+
+        /// Set the profile of the user with the label and append new users to the index.
+        /// Return the replaced profile if there is, which is archived.
+        fn register_profile(
+            &mut self,
+            user: AccountId,
+            label: &str,
+            profile: AccountId,
+        ) -> Option<AccountId> {
+            let old_profile = self.profile_of(user, label);
+            let mut labels = match self.user_labels.get(user) {
+                Some(labels) => labels,
+                None => {
+                    let index = self.indexed_users();
+                    self.user_index.insert(index, &user);
+                    self.indexed_users.set(&(index + 1));
+                    Vec::new()
+                }
+            };
+            if !labels.iter().any(|l| l == label) {
+                labels.push(label.into());
+            }
+            self.user_labels.insert(user, &labels);
+            self.profiles.insert((user, label), &profile);
+            self.profile_owners.insert(profile, &user);
+            if let Some(old_profile) = old_profile.filter(|old| *old != profile) {
+                self.profile_owners.insert(old_profile, &user);
+                self.archive_profile(user, old_profile);
+            }
+            old_profile
+        }
+
+        fn archive_profile(&mut self, user: AccountId, profile: AccountId) {
+            let mut archived = self.archived_profiles.get(user).unwrap_or_default();
+            if !archived.contains(&profile) {
+                archived.push(profile);
+                self.archived_profiles.insert(user, &archived);
+            }
+        }
+
+        fn profile_of(&self, user: AccountId, label: &str) -> Option<AccountId> {
+            self.profiles.get((user, label))
+        }
+
+        fn user_profiles(&self, user: AccountId) -> Vec<(String, AccountId)> {
+            self.user_labels
+                .get(user)
+                .unwrap_or_default()
+                .into_iter()
+                .filter_map(|label| {
+                    let profile = self.profiles.get((user, &label))?;
+                    Some((label, profile))
+                })
+                .collect()
+        }
+
+        fn indexed_users(&self) -> u64 {
+            self.indexed_users.get().unwrap_or_default()
+        }
+
         /// Return BadOrigin error if the caller is not the owner.
         fn ensure_owner(&self) -> Result<()> {
-            if self.env().caller() == self.owner {
-                Ok(())
-            } else {
-                Err(Error::BadOrigin)
+            self.ownership
+                .ensure_owner(&self.env().caller())
+                .or(Err(Error::BadOrigin))
+        }
+    }
+
+    impl Ownable for BrickProfileFactory {
+        #[ink(message, selector = 0xfeaea4fa)]
+        fn owner(&self) -> AccountId {
+            self.ownership.owner_account()
+        }
+
+        #[ink(message)]
+        fn ownership_renounced(&self) -> bool {
+            self.ownership.is_renounced()
+        }
+
+        #[ink(message)]
+        fn pending_owner(&self) -> Option<AccountId> {
+            self.ownership.pending_owner()
+        }
+
+        #[ink(message)]
+        fn propose_owner(&mut self, new_owner: Option<AccountId>) -> ownership::Result<()> {
+            self.ownership
+                .propose_owner(&self.env().caller(), new_owner)
+        }
+
+        #[ink(message)]
+        fn accept_ownership(&mut self) -> ownership::Result<()> {
+            let transfer = self.ownership.accept_ownership(&self.env().caller())?;
+            self.env().emit_event(OwnershipTransferred {
+                previous_owner: transfer.previous_owner,
+                new_owner: transfer.new_owner,
+            });
+            Ok(())
+        }
+
+        #[ink(message)]
+        fn renounce_ownership(&mut self) -> ownership::Result<()> {
+            let transfer = self.ownership.renounce_ownership(&self.env().caller())?;
+            self.env().emit_event(OwnershipTransferred {
+                previous_owner: transfer.previous_owner,
+                new_owner: transfer.new_owner,
+            });
+            Ok(())
+        }
+    }
+
+    fn ensure_valid_label(label: &str) -> Result<()> {
+        if label.is_empty()
+            || label.len() > MAX_LABEL_LEN
+            || !label
+                .chars()
+                .all(|c| c.is_ascii_alphanumeric() || c == '_' || c == '-')
+        {
+            return Err(Error::InvalidLabel);
+        }
+        Ok(())
+    }
+
+    fn profile_salt(owner: &AccountId, label: &str, generation: u32) -> [u8; 32] {
+        let mut salt = <Blake2x256 as HashOutput>::Type::default();
+        ink::env::hash_encoded::<Blake2x256, _>(
+            &(PROFILE_SALT_PREFIX, owner, label, generation),
+            &mut salt,
+        );
+        salt
+    }
+
+    /// The address pink derives for a contract, see `contract_id_preimage` of phala-types.
+    fn contract_address(
+        deployer: &AccountId,
+        code_hash: &Hash,
+        cluster_id: &Hash,
+        salt: &[u8],
+    ) -> AccountId {
+        let mut preimage = Vec::new();
+        preimage.extend_from_slice(deployer.as_ref());
+        preimage.extend_from_slice(code_hash.as_ref());
+        preimage.extend_from_slice(cluster_id.as_ref());
+        preimage.extend_from_slice(salt);
+        let mut address = <Blake2x256 as HashOutput>::Type::default();
+        ink::env::hash_bytes::<Blake2x256>(&preimage, &mut address);
+        AccountId::from(address)
+    }
+
+    fn upgrade_profile(profile: AccountId, code_hash: Hash) -> UpgradeStatus {
+        let mut user_profile = BrickProfileRef::from_account_id(profile);
+        match user_profile.call_mut().upgrade(code_hash).try_invoke() {
+            Ok(Ok(Ok(()))) => UpgradeStatus::Upgraded,
+            Ok(Ok(Err(brick_profile::Error::BadOrigin))) => UpgradeStatus::NotOptedIn,
+            Ok(Ok(Err(err))) => UpgradeStatus::Failed(format!("{:?}", err)),
+            // The selector of `upgrade` is unknown to the profile
+            Ok(Err(ink::LangError::CouldNotReadInput)) => UpgradeStatus::Unsupported,
+            Ok(Err(err)) => UpgradeStatus::Failed(format!("{:?}", err)),
+            Err(err) => UpgradeStatus::Failed(format!("{:?}", err)),
+        }
+    }
+
+    fn ensure_valid_template(template: &ProfileTemplate) -> Result<()> {
+        if template.accounts.iter().any(|rpcs| rpcs.is_empty()) {
+            return Err(Error::InvalidTemplate("no rpc endpoint".into()));
+        }
+        for workflow in template.workflows.iter() {
+            for (role, index) in workflow.bindings.iter() {
+                if *index as usize >= template.accounts.len() {
+                    return Err(Error::InvalidTemplate(format!(
+                        "no account {index} for role {role} of {}",
+                        workflow.name
+                    )));
+                }
             }
         }
+        Ok(())
     }
 
     #[cfg(test)]
     mod tests {
+        use super::*;
+
         #[ink::test]
         fn it_works() {
             let result = 2 + 2;
             assert_eq!(result, 4);
         }
+
+        #[ink::test]
+        fn user_registry_works() {
+            let accounts = ink::env::test::default_accounts::<pink::PinkEnvironment>();
+            let mut factory = BrickProfileFactory::new(Hash::from([0u8; 32]));
+            let profile = |n: u8| AccountId::from([n; 32]);
+
+            // Moved over from an older factory page by page
+            factory
+                .import_user_profiles(vec![
+                    (accounts.bob, profile(1)),
+                    (accounts.charlie, profile(2)),
+                ])
+                .unwrap();
+            factory
+                .import_user_profiles(vec![
+                    (accounts.charlie, profile(3)),
+                    (accounts.django, profile(4)),
+                ])
+                .unwrap();
+            assert_eq!(factory.user_count(), 3);
+            let default = String::from(DEFAULT_LABEL);
+            assert_eq!(
+                factory.get_user_profiles(0, 10).unwrap(),
+                vec![
+                    (accounts.bob, default.clone(), profile(1)),
+                    (accounts.charlie, default.clone(), profile(3)),
+                    (accounts.django, default.clone(), profile(4))
+                ]
+            );
+            assert_eq!(
+                factory.get_user_profiles(1, 1).unwrap(),
+                vec![(accounts.charlie, default, profile(3))]
+            );
+            assert!(factory.get_user_profiles(3, 10).unwrap().is_empty());
+            // The overwritten profile is archived
+            assert_eq!(
+                factory.get_archived_profiles(accounts.charlie),
+                vec![profile(2)]
+            );
+            assert_eq!(
+                factory.get_profile_owner(profile(2)),
+                Some(accounts.charlie)
+            );
+
+            ink::env::test::set_caller::<pink::PinkEnvironment>(accounts.bob);
+            assert_eq!(factory.get_user_profile_address().unwrap(), profile(1));
+            assert!(matches!(
+                factory.import_user_profiles(vec![(accounts.bob, profile(5))]),
+                Err(Error::BadOrigin)
+            ));
+        }
+
+        #[ink::test]
+        fn legacy_user_import_works() {
+            let accounts = ink::env::test::default_accounts::<pink::PinkEnvironment>();
+            let mut factory = BrickProfileFactory::new(Hash::from([0u8; 32]));
+            let profile = |n: u8| AccountId::from([n; 32]);
+
+            // The reply of `get_user_profiles` of the old factory, which returns its
+            // `BTreeMap<AccountId, BrickProfileRef>` as pairs
+            let legacy_users: alloc::collections::BTreeMap<AccountId, AccountId> = [
+                (accounts.django, profile(1)),
+                (accounts.bob, profile(2)),
+                (accounts.eve, profile(3)),
+            ]
+            .into_iter()
+            .collect();
+            let legacy_output = Ok::<_, ink::LangError>(Ok::<_, u8>(legacy_users.clone())).encode();
+            let users: Vec<(AccountId, AccountId)> =
+                ink::MessageResult::<core::result::Result<_, u8>>::decode(&mut &legacy_output[..])
+                    .unwrap()
+                    .unwrap()
+                    .unwrap();
+
+            for batch in users.chunks(2) {
+                factory.import_user_profiles(batch.to_vec()).unwrap();
+            }
+            assert_eq!(factory.user_count(), legacy_users.len() as u64);
+            let default = String::from(DEFAULT_LABEL);
+            assert_eq!(
+                factory.get_user_profiles(0, 10).unwrap(),
+                legacy_users
+                    .into_iter()
+                    .map(|(user, profile)| (user, default.clone(), profile))
+                    .collect::<Vec<_>>()
+            );
+            assert_eq!(factory.get_profile_owner(profile(3)), Some(accounts.eve));
+        }
+
+        #[ink::test]
+        fn labeled_profiles_works() {
+            let accounts = ink::env::test::default_accounts::<pink::PinkEnvironment>();
+            let mut factory = BrickProfileFactory::new(Hash::from([0u8; 32]));
+            let profile = |n: u8| AccountId::from([n; 32]);
+
+            assert_eq!(
+                factory.register_profile(accounts.bob, DEFAULT_LABEL, profile(1)),
+                None
+            );
+            assert_eq!(
+                factory.register_profile(accounts.bob, "oracle", profile(2)),
+                None
+            );
+            assert_eq!(
+                factory.register_profile(accounts.bob, "oracle", profile(3)),
+                Some(profile(2))
+            );
+            assert_eq!(factory.user_count(), 1);
+            assert_eq!(
+                factory.get_profiles_of(accounts.bob),
+                vec![
+                    (DEFAULT_LABEL.into(), profile(1)),
+                    ("oracle".into(), profile(3))
+                ]
+            );
+            assert_eq!(
+                factory.get_archived_profiles(accounts.bob),
+                vec![profile(2)]
+            );
+            assert_eq!(factory.get_profile_owner(profile(2)), Some(accounts.bob));
+            assert_eq!(factory.get_profile_owner(profile(4)), None);
+
+            ink::env::test::set_caller::<pink::PinkEnvironment>(accounts.bob);
+            assert_eq!(
+                factory
+                    .get_labeled_profile_address("oracle".into())
+                    .unwrap(),
+                profile(3)
+            );
+            assert!(matches!(
+                factory.create_labeled_profile("oracle".into()),
+                Err(Error::NoDuplicatedUserProfile)
+            ));
+            assert!(matches!(
+                factory.force_create_labeled_profile("<script>".into()),
+                Err(Error::InvalidLabel)
+            ));
+        }
+
+        #[ink::test]
+        fn templates_works() {
+            let accounts = ink::env::test::default_accounts::<pink::PinkEnvironment>();
+            let mut factory = BrickProfileFactory::new(Hash::from([0u8; 32]));
+            let template = ProfileTemplate {
+                js_runner: accounts.django,
+                accounts: vec![vec!["https://a.com".into(), "https://b.com".into()]],
+                workflows: vec![TemplateWorkflow {
+                    name: "oracle".into(),
+                    commandline: "[]".into(),
+                    bindings: vec![("default".into(), 0)],
+                }],
+            };
+            factory
+                .set_template("lens".into(), template.clone())
+                .unwrap();
+            assert_eq!(factory.get_template("lens".into()).unwrap(), template);
+            assert_eq!(factory.get_template_names(), vec![String::from("lens")]);
+
+            let mut bad_template = template.clone();
+            bad_template.workflows[0]
+                .bindings
+                .push(("gas_payer".into(), 1));
+            assert!(matches!(
+                factory.set_template("bad".into(), bad_template),
+                Err(Error::InvalidTemplate(_))
+            ));
+            let overrides = TemplateOverrides {
+                rpcs: vec![(1, vec!["https://c.com".into()])],
+                ..Default::default()
+            };
+            assert!(matches!(
+                factory.setup_user_profile_from_template("lens".into(), overrides),
+                Err(Error::InvalidTemplate(_))
+            ));
+            assert!(matches!(
+                factory.setup_user_profile_from_template("missing".into(), Default::default()),
+                Err(Error::TemplateNotFound)
+            ));
+            factory.register_profile(accounts.alice, DEFAULT_LABEL, AccountId::from([1; 32]));
+            assert!(matches!(
+                factory.setup_user_profile_from_template("lens".into(), Default::default()),
+                Err(Error::NoDuplicatedUserProfile)
+            ));
+
+            factory.remove_template("lens".into()).unwrap();
+            assert!(factory.get_template_names().is_empty());
+            assert!(matches!(
+                factory.remove_template("lens".into()),
+                Err(Error::TemplateNotFound)
+            ));
+
+            ink::env::test::set_caller::<pink::PinkEnvironment>(accounts.bob);
+            assert!(matches!(
+                factory.set_template("lens".into(), template),
+                Err(Error::BadOrigin)
+            ));
+        }
+
+        #[ink::test]
+        fn upgrade_rollout_works() {
+            let accounts = ink::env::test::default_accounts::<pink::PinkEnvironment>();
+            let mut factory = BrickProfileFactory::new(Hash::from([0u8; 32]));
+            let profile = AccountId::from([1; 32]);
+            let code_hash = Hash::from([2u8; 32]);
+            assert!(matches!(
+                factory.upgrade_profiles(10),
+                Err(Error::NoUpgradeScheduled)
+            ));
+            assert_eq!(factory.get_upgrade_status(profile), None);
+
+            factory.schedule_profile_upgrade(code_hash).unwrap();
+            assert_eq!(factory.profile_code_hash(), code_hash);
+            assert_eq!(
+                factory.get_upgrade_rollout(),
+                Some(UpgradeRollout {
+                    code_hash,
+                    next_user: 0,
+                })
+            );
+            assert_eq!(factory.upgrade_profiles(10).unwrap(), 0);
+            // Unknown to the factory
+            assert_eq!(factory.get_upgrade_status(profile), None);
+
+            factory.register_profile(accounts.bob, DEFAULT_LABEL, profile);
+            assert_eq!(
+                factory.get_upgrade_status(profile),
+                Some(UpgradeStatus::Pending)
+            );
+            // Reported against the latest rollout only
+            factory
+                .upgrade_status
+                .insert(profile, &(code_hash, UpgradeStatus::NotOptedIn));
+            assert_eq!(
+                factory.get_upgrade_status(profile),
+                Some(UpgradeStatus::NotOptedIn)
+            );
+            factory
+                .schedule_profile_upgrade(Hash::from([3u8; 32]))
+                .unwrap();
+            assert_eq!(
+                factory.get_upgrade_status(profile),
+                Some(UpgradeStatus::Pending)
+            );
+
+            ink::env::test::set_caller::<pink::PinkEnvironment>(accounts.bob);
+            assert!(matches!(
+                factory.schedule_profile_upgrade(code_hash),
+                Err(Error::BadOrigin)
+            ));
+            assert!(matches!(
+                factory.upgrade_profiles(10),
+                Err(Error::BadOrigin)
+            ));
+        }
+
+        #[ink::test]
+        fn profile_address_prediction_works() {
+            let accounts = ink::env::test::default_accounts::<pink::PinkEnvironment>();
+            let mut factory = BrickProfileFactory::new(Hash::from([0u8; 32]));
+            assert!(matches!(
+                factory.predict_profile_address(accounts.bob, "oracle".into()),
+                Err(Error::ClusterIdNotSet)
+            ));
+            factory.set_cluster_id(Hash::from([1u8; 32])).unwrap();
+            assert_eq!(factory.cluster_id(), Some(Hash::from([1u8; 32])));
+
+            let predict = |factory: &BrickProfileFactory, user, label: &str| {
+                factory.predict_profile_address(user, label.into()).unwrap()
+            };
+            let address = predict(&factory, accounts.bob, "oracle");
+            assert_eq!(predict(&factory, accounts.bob, "oracle"), address);
+            assert_ne!(predict(&factory, accounts.bob, DEFAULT_LABEL), address);
+            assert_ne!(predict(&factory, accounts.charlie, "oracle"), address);
+            assert!(matches!(
+                factory.predict_profile_address(accounts.bob, "<script>".into()),
+                Err(Error::InvalidLabel)
+            ));
+
+            // Replaced profiles are salted with the next generation
+            factory
+                .profile_generations
+                .insert((accounts.bob, "oracle"), &1);
+            assert_ne!(predict(&factory, accounts.bob, "oracle"), address);
+            // Depends on the profile code hash
+            let address = predict(&factory, accounts.bob, "oracle");
+            factory
+                .set_profile_code_hash(Hash::from([2u8; 32]))
+                .unwrap();
+            assert_ne!(predict(&factory, accounts.bob, "oracle"), address);
+
+            ink::env::test::set_caller::<pink::PinkEnvironment>(accounts.bob);
+            assert!(matches!(
+                factory.set_cluster_id(Hash::from([1u8; 32])),
+                Err(Error::BadOrigin)
+            ));
+        }
     }
 }
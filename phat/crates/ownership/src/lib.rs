@@ -0,0 +1,247 @@
+//! Two-step ownership shared by the Phat contracts.
+//!
+//! The owner proposes a new owner, who must accept the ownership before it takes effect, so a
+//! mistyped account can never lock the contract. The owner can also explicitly renounce the
+//! ownership, after which no one is the owner.
+//!
+//! [`Ownership`] takes the place of the `owner: AccountId` field of a contract storage. It keeps
+//! the owner as the same packed field and puts the rest in lazy fields, so the storage of the
+//! already deployed contracts still decodes.
+//!
+//! The contracts expose the messages by implementing [`Ownable`]. Its `owner` message keeps the
+//! selector and the return type of the `owner` message the contracts had before, so the existing
+//! clients keep working. ink! 4 events can only be
+//! declared in the contract module, so each contract declares its own `OwnershipTransferred`
+//! event and emits the [`Transfer`] returned here.
+
+#![cfg_attr(not(feature = "std"), no_std)]
+
+use ink::primitives::AccountId;
+use ink::storage::Lazy;
+use scale::{Decode, Encode};
+
+#[derive(Encode, Decode, Debug, PartialEq, Eq)]
+#[cfg_attr(feature = "std", derive(scale_info::TypeInfo))]
+pub enum Error {
+    /// The caller is not the owner
+    NotOwner,
+    /// The caller is not the proposed owner
+    NotPendingOwner,
+}
+
+pub type Result<T> = core::result::Result<T, Error>;
+
+/// The ownership messages of a contract.
+#[ink::trait_definition]
+pub trait Ownable {
+    /// Gets the owner of the contract.
+    ///
+    /// The account is kept after the ownership is renounced, check `ownership_renounced`.
+    #[ink(message, selector = 0xfeaea4fa)]
+    fn owner(&self) -> AccountId;
+
+    /// Whether the ownership is renounced, after which no one is the owner.
+    #[ink(message)]
+    fn ownership_renounced(&self) -> bool;
+
+    /// Gets the proposed owner waiting to accept the ownership.
+    #[ink(message)]
+    fn pending_owner(&self) -> Option<AccountId>;
+
+    /// Proposes a new owner, who must call `accept_ownership` to take over (only owner).
+    ///
+    /// Pass `None` to cancel the proposal.
+    #[ink(message)]
+    fn propose_owner(&mut self, new_owner: Option<AccountId>) -> Result<()>;
+
+    /// Accepts the proposed ownership (only the proposed owner).
+    #[ink(message)]
+    fn accept_ownership(&mut self) -> Result<()>;
+
+    /// Renounces the ownership for good, this locks the owner-only configuration (only owner).
+    #[ink(message)]
+    fn renounce_ownership(&mut self) -> Result<()>;
+}
+
+/// A change of the owner, `None` means no owner.
+#[derive(Debug, PartialEq, Eq)]
+pub struct Transfer {
+    pub previous_owner: Option<AccountId>,
+    pub new_owner: Option<AccountId>,
+}
+
+#[ink::storage_item]
+#[derive(Debug)]
+pub struct Ownership {
+    /// The owner, kept even after the ownership is renounced
+    owner: AccountId,
+    pending_owner: Lazy<Option<AccountId>>,
+    renounced: Lazy<bool>,
+}
+
+impl Ownership {
+    pub fn new(owner: AccountId) -> Self {
+        Self {
+            owner,
+            pending_owner: Default::default(),
+            renounced: Default::default(),
+        }
+    }
+
+    /// The current owner, `None` if the ownership is renounced.
+    pub fn owner(&self) -> Option<AccountId> {
+        if self.is_renounced() {
+            None
+        } else {
+            Some(self.owner)
+        }
+    }
+
+    /// The owner account, which is kept after the ownership is renounced.
+    pub fn owner_account(&self) -> AccountId {
+        self.owner
+    }
+
+    pub fn is_renounced(&self) -> bool {
+        self.renounced.get().unwrap_or_default()
+    }
+
+    /// The proposed owner waiting to accept the ownership.
+    pub fn pending_owner(&self) -> Option<AccountId> {
+        self.pending_owner.get().flatten()
+    }
+
+    pub fn is_owner(&self, account: &AccountId) -> bool {
+        self.owner().as_ref() == Some(account)
+    }
+
+    pub fn ensure_owner(&self, caller: &AccountId) -> Result<()> {
+        if self.is_owner(caller) {
+            Ok(())
+        } else {
+            Err(Error::NotOwner)
+        }
+    }
+
+    /// Proposes a new owner (only owner), `None` cancels the pending proposal.
+    pub fn propose_owner(
+        &mut self,
+        caller: &AccountId,
+        new_owner: Option<AccountId>,
+    ) -> Result<()> {
+        self.ensure_owner(caller)?;
+        self.pending_owner.set(&new_owner);
+        Ok(())
+    }
+
+    /// Accepts the proposed ownership (only the pending owner).
+    pub fn accept_ownership(&mut self, caller: &AccountId) -> Result<Transfer> {
+        if self.pending_owner().as_ref() != Some(caller) {
+            return Err(Error::NotPendingOwner);
+        }
+        let previous_owner = self.owner();
+        self.owner = *caller;
+        self.pending_owner.set(&None);
+        Ok(Transfer {
+            previous_owner,
+            new_owner: Some(*caller),
+        })
+    }
+
+    /// Gives up the ownership for good (only owner), the pending proposal is canceled as well.
+    pub fn renounce_ownership(&mut self, caller: &AccountId) -> Result<Transfer> {
+        self.ensure_owner(caller)?;
+        let previous_owner = self.owner();
+        self.renounced.set(&true);
+        self.pending_owner.set(&None);
+        Ok(Transfer {
+            previous_owner,
+            new_owner: None,
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use ink::storage::traits::Storable;
+
+    #[ink::test]
+    fn two_step_transfer_works() {
+        let alice = AccountId::from([1; 32]);
+        let bob = AccountId::from([2; 32]);
+        let mut ownership = Ownership::new(alice);
+
+        assert_eq!(
+            ownership.propose_owner(&bob, Some(bob)),
+            Err(Error::NotOwner)
+        );
+        ownership.propose_owner(&alice, Some(bob)).unwrap();
+        assert_eq!(ownership.pending_owner(), Some(bob));
+        // The ownership is not transferred until accepted
+        assert!(ownership.is_owner(&alice));
+        assert_eq!(
+            ownership.accept_ownership(&alice),
+            Err(Error::NotPendingOwner)
+        );
+
+        assert_eq!(
+            ownership.accept_ownership(&bob),
+            Ok(Transfer {
+                previous_owner: Some(alice),
+                new_owner: Some(bob),
+            })
+        );
+        assert_eq!(ownership.owner(), Some(bob));
+        assert_eq!(ownership.pending_owner(), None);
+        assert_eq!(
+            ownership.accept_ownership(&bob),
+            Err(Error::NotPendingOwner)
+        );
+    }
+
+    #[ink::test]
+    fn renounce_works() {
+        let alice = AccountId::from([1; 32]);
+        let bob = AccountId::from([2; 32]);
+        let mut ownership = Ownership::new(alice);
+
+        ownership.propose_owner(&alice, Some(bob)).unwrap();
+        assert_eq!(ownership.renounce_ownership(&bob), Err(Error::NotOwner));
+        assert_eq!(
+            ownership.renounce_ownership(&alice),
+            Ok(Transfer {
+                previous_owner: Some(alice),
+                new_owner: None,
+            })
+        );
+        assert_eq!(ownership.owner(), None);
+        assert!(ownership.is_renounced());
+        assert_eq!(ownership.owner_account(), alice);
+        assert!(!ownership.is_owner(&alice));
+        // The pending proposal is canceled
+        assert_eq!(
+            ownership.accept_ownership(&bob),
+            Err(Error::NotPendingOwner)
+        );
+        assert_eq!(
+            ownership.propose_owner(&alice, Some(bob)),
+            Err(Error::NotOwner)
+        );
+    }
+
+    #[ink::test]
+    fn encodes_as_owner_account() {
+        let alice = AccountId::from([1; 32]);
+        let mut ownership = Ownership::new(alice);
+        ownership.propose_owner(&alice, Some(alice)).unwrap();
+
+        let mut encoded = Vec::new();
+        ownership.encode(&mut encoded);
+        assert_eq!(encoded, scale::Encode::encode(&alice));
+
+        let decoded = Ownership::decode(&mut &encoded[..]).unwrap();
+        assert_eq!(decoded.owner(), Some(alice));
+        assert_eq!(decoded.pending_owner(), Some(alice));
+    }
+}